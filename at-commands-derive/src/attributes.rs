@@ -0,0 +1,89 @@
+//! Parsing of the `#[at(...)]` attributes
+
+use syn::{Attribute, LitStr, Type};
+
+/// The kind of command, mirroring the builder constructors
+#[derive(Clone, Copy)]
+pub enum CommandKind {
+    Test,
+    Query,
+    Set,
+    Execute,
+}
+
+/// The options that can be given with the `#[at(...)]` attribute on a struct
+#[derive(Default)]
+pub struct ContainerAttributes {
+    pub cmd: Option<LitStr>,
+    pub kind: Option<(CommandKind, LitStr)>,
+    pub response: Option<Type>,
+    pub prefix: Option<LitStr>,
+}
+
+impl ContainerAttributes {
+    pub fn parse(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut result = Self::default();
+
+        for attribute in attributes.iter().filter(|a| a.path().is_ident("at")) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("cmd") {
+                    result.cmd = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("kind") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    let kind = match value.value().as_str() {
+                        "test" => CommandKind::Test,
+                        "query" => CommandKind::Query,
+                        "set" => CommandKind::Set,
+                        "execute" => CommandKind::Execute,
+                        _ => {
+                            return Err(syn::Error::new(
+                                value.span(),
+                                "expected one of `test`, `query`, `set` or `execute`",
+                            ))
+                        }
+                    };
+                    result.kind = Some((kind, value));
+                    Ok(())
+                } else if meta.path.is_ident("response") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    result.response = Some(value.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("prefix") {
+                    result.prefix = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown `at` attribute"))
+                }
+            })?;
+        }
+
+        Ok(result)
+    }
+}
+
+/// The options that can be given with the `#[at(...)]` attribute on a field
+#[derive(Default)]
+pub struct FieldAttributes {
+    /// The string is not quoted
+    pub raw: bool,
+}
+
+impl FieldAttributes {
+    pub fn parse(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut result = Self::default();
+
+        for attribute in attributes.iter().filter(|a| a.path().is_ident("at")) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("raw") {
+                    result.raw = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown `at` attribute"))
+                }
+            })?;
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,134 @@
+//! Implementation of the `AtCommand` derive
+
+use crate::attributes::{CommandKind, ContainerAttributes};
+use crate::field::{self, ParameterKind};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::visit_mut::VisitMut;
+use syn::{Data, DeriveInput, Lifetime};
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let attributes = ContainerAttributes::parse(&input.attrs)?;
+    let cmd = attributes.cmd.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "missing `#[at(cmd = \"...\")]` attribute",
+        )
+    })?;
+    let (kind, kind_literal) = attributes.kind.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "missing `#[at(kind = \"...\")]` attribute",
+        )
+    })?;
+    if let Some(prefix) = attributes.prefix {
+        return Err(syn::Error::new(
+            prefix.span(),
+            "`prefix` is only used by `AtResponse`, use `response` to set the response type",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`AtCommand` can only be derived for structs",
+            ))
+        }
+    };
+
+    if !matches!(kind, CommandKind::Set) && !fields.is_empty() {
+        return Err(syn::Error::new(
+            kind_literal.span(),
+            "only set commands can have parameters",
+        ));
+    }
+
+    let parameters = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let member = field::member(index, field);
+
+            Ok(match ParameterKind::from_field(field)? {
+                ParameterKind::Int => quote!(.with_int_parameter_as(self.#member)),
+                ParameterKind::String => quote!(.with_string_parameter(self.#member)),
+                ParameterKind::RawString => quote!(.with_raw_parameter(self.#member)),
+                ParameterKind::OptionalInt => quote!(.with_optional_int_parameter_as(self.#member)),
+                ParameterKind::OptionalString => {
+                    quote!(.with_optional_string_parameter(self.#member))
+                }
+                // An empty raw parameter is the same as an empty parameter
+                ParameterKind::OptionalRawString => {
+                    quote!(.with_raw_parameter(self.#member.unwrap_or("")))
+                }
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let create = match kind {
+        CommandKind::Test => quote!(create_test),
+        CommandKind::Query => quote!(create_query),
+        CommandKind::Set => quote!(create_set),
+        CommandKind::Execute => quote!(create_execute),
+    };
+
+    let (response, parse_response) = match attributes.response {
+        Some(mut response) => {
+            ResponseLifetime.visit_type_mut(&mut response);
+            let parse = quote! {
+                <#response as ::at_commands::command::AtResponse<'__response>>::parse(buffer)
+            };
+            (response, parse)
+        }
+        None => {
+            let parse = quote! {
+                ::at_commands::parser::CommandParser::parse(buffer)
+                    .expect_identifier(b"\r\nOK\r\n")
+                    .finish()
+            };
+            (syn::parse_quote!(()), parse)
+        }
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::at_commands::command::AtCommand for #name #ty_generics #where_clause {
+            type Response<'__response> = #response;
+
+            fn write<'__buffer>(
+                &self,
+                buffer: &'__buffer mut [u8],
+            ) -> ::core::result::Result<&'__buffer [u8], usize> {
+                ::at_commands::builder::CommandBuilder::#create(buffer, true)
+                    .named(#cmd)
+                    #(#parameters)*
+                    .finish()
+            }
+
+            fn parse_response<'__response>(
+                &self,
+                buffer: &'__response [u8],
+            ) -> ::core::result::Result<
+                Self::Response<'__response>,
+                ::at_commands::parser::ParseError<'__response>,
+            > {
+                #parse_response
+            }
+        }
+    })
+}
+
+/// Replaces the `'_` lifetimes in the response type with the lifetime of the response buffer
+struct ResponseLifetime;
+
+impl VisitMut for ResponseLifetime {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = Lifetime::new("'__response", lifetime.span());
+        }
+    }
+}
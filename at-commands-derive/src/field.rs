@@ -0,0 +1,122 @@
+//! Mapping of fields to parameter kinds
+
+use crate::attributes::FieldAttributes;
+use syn::{Field, GenericArgument, Member, PathArguments, Type};
+
+/// The types of parameters that a field can represent
+#[derive(Clone, Copy)]
+pub enum ParameterKind {
+    Int,
+    String,
+    RawString,
+    OptionalInt,
+    OptionalString,
+    OptionalRawString,
+}
+
+impl ParameterKind {
+    /// Determines the kind of parameter by looking at the type and attributes of the field.
+    ///
+    /// This is done purely on the syntax, so type aliases are not recognised.
+    pub fn from_field(field: &Field) -> syn::Result<Self> {
+        let attributes = FieldAttributes::parse(&field.attrs)?;
+        let kind = Self::from_type(&field.ty)?;
+
+        if !attributes.raw {
+            return Ok(kind);
+        }
+
+        match kind {
+            ParameterKind::String => Ok(ParameterKind::RawString),
+            ParameterKind::OptionalString => Ok(ParameterKind::OptionalRawString),
+            _ => Err(syn::Error::new_spanned(
+                &field.ty,
+                "only string fields can be `raw`",
+            )),
+        }
+    }
+
+    fn from_type(ty: &Type) -> syn::Result<Self> {
+        if let Some(inner) = option_inner_type(ty) {
+            return match Self::from_type(inner)? {
+                ParameterKind::Int => Ok(ParameterKind::OptionalInt),
+                ParameterKind::String => Ok(ParameterKind::OptionalString),
+                _ => Err(syn::Error::new_spanned(
+                    ty,
+                    "nested options are not supported",
+                )),
+            };
+        }
+
+        if is_int(ty) {
+            Ok(ParameterKind::Int)
+        } else if is_str_reference(ty) {
+            Ok(ParameterKind::String)
+        } else {
+            Err(syn::Error::new_spanned(
+                ty,
+                "unsupported field type, expected an int, `&str` or an `Option` of those",
+            ))
+        }
+    }
+}
+
+/// Gets the name or index of the field that can be used to access it
+pub fn member(index: usize, field: &Field) -> Member {
+    match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(index.into()),
+    }
+}
+
+/// Gets the type of the field, or the `T` if it's an `Option<T>`
+pub fn value_type(field: &Field) -> &Type {
+    option_inner_type(&field.ty).unwrap_or(&field.ty)
+}
+
+/// The last segment of a path type, if the type is a path
+fn last_path_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+        _ => None,
+    }
+}
+
+fn is_int(ty: &Type) -> bool {
+    match last_path_segment(ty) {
+        Some(segment) if segment.arguments.is_empty() => {
+            matches!(
+                segment.ident.to_string().as_str(),
+                "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64"
+            )
+        }
+        _ => false,
+    }
+}
+
+fn is_str_reference(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_none() => {
+            matches!(last_path_segment(&reference.elem), Some(segment) if segment.ident == "str")
+        }
+        _ => false,
+    }
+}
+
+/// Gets the `T` out of an `Option<T>`
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    let segment = last_path_segment(ty)?;
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+            match arguments.args.first() {
+                Some(GenericArgument::Type(inner)) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
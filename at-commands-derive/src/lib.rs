@@ -0,0 +1,48 @@
+//! Derive macros for the [at-commands](https://docs.rs/at-commands) crate.
+//!
+//! Use them through the `derive` feature of that crate instead of depending on this crate directly.
+
+mod attributes;
+mod command;
+mod field;
+mod response;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Implements `AtCommand` for a struct.
+///
+/// The struct must have an `#[at(cmd = "...", kind = "...")]` attribute.
+/// The kind is one of `test`, `query`, `set` or `execute`.
+///
+/// The fields become the parameters of a set command, in order of declaration.
+/// Int fields become int parameters, `&str` fields become string parameters
+/// and `Option`s of those become optional parameters.
+/// A `#[at(raw)]` attribute on a string field makes it an unquoted parameter.
+///
+/// The response type can be set with `#[at(response = "...")]` and must implement `AtResponse`.
+/// A `'_` lifetime in it refers to the lifetime of the response buffer.
+/// Without it, the response is `()` and expects the command to be answered with `\r\nOK\r\n`.
+#[proc_macro_derive(AtCommand, attributes(at))]
+pub fn derive_at_command(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    command::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `AtResponse` for a struct.
+///
+/// The struct must have an `#[at(prefix = "...")]` attribute with the identifier the response starts with.
+///
+/// The fields are parsed as the parameters after the prefix, in order of declaration.
+/// Int fields are int parameters, `&str` fields are string parameters
+/// and `Option`s of those are optional parameters.
+/// A `#[at(raw)]` attribute on a string field makes it read a non-quoted string.
+#[proc_macro_derive(AtResponse, attributes(at))]
+pub fn derive_at_response(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    response::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
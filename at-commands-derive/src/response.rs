@@ -0,0 +1,120 @@
+//! Implementation of the `AtResponse` derive
+
+use crate::attributes::ContainerAttributes;
+use crate::field::{self, ParameterKind};
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, GenericParam, Lifetime, LifetimeParam};
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let attributes = ContainerAttributes::parse(&input.attrs)?;
+    let prefix = attributes.prefix.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "missing `#[at(prefix = \"...\")]` attribute",
+        )
+    })?;
+    if let Some((_, kind)) = attributes.kind {
+        return Err(syn::Error::new(
+            kind.span(),
+            "`kind` is only used by `AtCommand`",
+        ));
+    }
+    if let Some(cmd) = attributes.cmd {
+        return Err(syn::Error::new(
+            cmd.span(),
+            "`cmd` is only used by `AtCommand`",
+        ));
+    }
+    let prefix = syn::LitByteStr::new(prefix.value().as_bytes(), prefix.span());
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`AtResponse` can only be derived for structs",
+            ))
+        }
+    };
+
+    // Every field is taken out of the parser right away,
+    // so there's no limit to the amount of fields.
+    let mut steps = Vec::new();
+    let mut values = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let kind = ParameterKind::from_field(field)?;
+        let expect = match kind {
+            ParameterKind::Int => {
+                let ty = field::value_type(field);
+                quote!(expect_int_parameter_as::<#ty>)
+            }
+            ParameterKind::String => quote!(expect_string_parameter),
+            ParameterKind::RawString => quote!(expect_raw_string),
+            ParameterKind::OptionalInt => {
+                let ty = field::value_type(field);
+                quote!(expect_optional_int_parameter_as::<#ty>)
+            }
+            ParameterKind::OptionalString => quote!(expect_optional_string_parameter),
+            ParameterKind::OptionalRawString => quote!(expect_optional_raw_string),
+        };
+        let value = format_ident!("__field_{}", index);
+        steps.push(quote! {
+            let ((#value,), parser) = parser.#expect().take_data();
+        });
+        values.push((field::member(index, field), value));
+    }
+
+    let construct = match fields {
+        Fields::Named(_) => {
+            let (members, values): (Vec<_>, Vec<_>) = values.into_iter().unzip();
+            quote!(Self { #(#members: #values),* })
+        }
+        Fields::Unnamed(_) => {
+            let values = values.into_iter().map(|(_, value)| value);
+            quote!(Self(#(#values),*))
+        }
+        Fields::Unit => quote!(Self),
+    };
+
+    // The response may borrow from the buffer through its lifetime.
+    // If it doesn't have one, we make one up for the trait.
+    let lifetimes = input.generics.lifetimes().collect::<Vec<_>>();
+    let (lifetime, mut impl_generics) = match lifetimes.as_slice() {
+        [] => {
+            let lifetime = Lifetime::new("'__response", Span::call_site());
+            let mut generics = input.generics.clone();
+            generics.params.insert(
+                0,
+                GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())),
+            );
+            (lifetime, generics)
+        }
+        [lifetime] => (lifetime.lifetime.clone(), input.generics.clone()),
+        [_, second, ..] => {
+            return Err(syn::Error::new_spanned(
+                second,
+                "`AtResponse` can only be derived for structs with at most one lifetime",
+            ))
+        }
+    };
+    impl_generics.where_clause = input.generics.where_clause.clone();
+
+    let name = &input.ident;
+    let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::at_commands::command::AtResponse<#lifetime> for #name #ty_generics #where_clause {
+            fn parse(
+                buffer: &#lifetime [u8],
+            ) -> ::core::result::Result<Self, ::at_commands::parser::ParseError<#lifetime>> {
+                let parser = ::at_commands::parser::CommandParser::parse(buffer)
+                    .expect_identifier(#prefix);
+                #(#steps)*
+                parser.finish()?;
+                ::core::result::Result::Ok(#construct)
+            }
+        }
+    })
+}
@@ -0,0 +1,1248 @@
+//! A terminal for talking to AT command devices, for bench work
+//!
+//! ```text
+//! at-cli [OPTIONS] <DEVICE>
+//! ```
+//!
+//! The device is one of:
+//! - a path, like `/dev/ttyUSB0` or `/dev/pts/3`. A serial port must already be set up, for example with `stty`.
+//! - `tcp:<host>:<port>`
+//! - `unix:<path>` for a unix socket
+//!
+//! The commands typed in are parsed and built again with the library before they are sent,
+//! so a command that the library can't handle shows up right away.
+//! The parameters are sent as they were typed.
+//! The responses are split into lines with the digester and their parameters are parsed and printed one by one.
+//! Lines that don't belong to the command that is sent are shown as URCs.
+//!
+//! With `--script` the commands in a file are sent one after the other and the responses are checked:
+//!
+//! ```text
+//! # Comments and empty lines are skipped
+//! AT+CSQ
+//! < +CSQ: 20,99
+//! < OK
+//! AT+CMGS="+31612345678"
+//! > Hello from the bench
+//! < +CMGS: 12
+//! < OK
+//! AT+CGMR
+//! ```
+//!
+//! A line with `>` is the data that is sent after the prompt, ended with a Ctrl-Z.
+//! The lines with `<` are the expected lines of the response,
+//! of which the last must be the final result code.
+//! If none of them is a line without a name, the lines without a name that come in,
+//! like a `RING`, are URCs and not part of the response.
+//! A command without expected lines may get any response.
+//! A command that gets no response before the timeout fails, and the script goes on with the next one.
+
+use at_commands::builder::{CommandBuilder, PayloadError};
+use at_commands::dce::{CommandKind, IncomingCommand};
+use at_commands::digester::{DigestItem, Digester};
+use at_commands::parser::CommandParser;
+use at_commands::response_builder::{ResponseBuilder, ResultCodeFormat};
+use at_commands::result_code::FinalResult;
+use at_commands::transcript::Recorder;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::net::TcpStream;
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The size of the buffers for a single command and a single line of the response
+const BUFFER_SIZE: usize = 1024;
+/// How long a read of the device waits before the terminal looks at the input again
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+const USAGE: &str = "\
+Usage: at-cli [OPTIONS] <DEVICE>
+
+The device is a path (like /dev/ttyUSB0 or a PTY), tcp:<host>:<port> or unix:<path>.
+
+Options:
+  --script <FILE>   Send the commands in the script and check the responses
+  --record <FILE>   Write a transcript of the session to the file
+  --timeout <MS>    How long to wait for a response [default: 5000]
+  --no-color        Don't color the output
+  -h, --help        Print this help
+";
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprint!("error: {}\n\n{}", error, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the session. Returns false if the script failed.
+fn run(options: &Options) -> Result<bool, CliError> {
+    let script = match &options.script {
+        Some(path) => Some(Script::parse(&fs::read_to_string(path)?)?),
+        None => None,
+    };
+
+    let device = Recorder::new(open_device(&options.device)?);
+    let mut terminal = Terminal::new(device, io::stdout(), options.color);
+
+    let result = match &script {
+        Some(script) => terminal.run_script(script, options.timeout),
+        None => terminal.run_interactive(options.timeout).map(|()| true),
+    };
+
+    // The transcript is most useful when something went wrong, so it's always written
+    if let Some(path) = &options.record {
+        let (device, _) = terminal.release();
+        let (_, transcript) = device.finish();
+        fs::write(path, transcript.to_string())?;
+    }
+
+    result
+}
+
+/// The command line options
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    device: String,
+    script: Option<String>,
+    record: Option<String>,
+    timeout: Duration,
+    color: bool,
+}
+
+impl Options {
+    /// Parses the arguments. Returns None if the help is asked for.
+    fn parse(arguments: impl IntoIterator<Item = String>) -> Result<Option<Self>, CliError> {
+        let mut arguments = arguments.into_iter();
+        let mut device = None;
+        let mut options = Options {
+            device: String::new(),
+            script: None,
+            record: None,
+            timeout: Duration::from_millis(5000),
+            color: true,
+        };
+
+        while let Some(argument) = arguments.next() {
+            let mut value = || {
+                arguments
+                    .next()
+                    .ok_or_else(|| CliError::Usage(format!("{} needs a value", argument)))
+            };
+
+            match argument.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--script" => options.script = Some(value()?),
+                "--record" => options.record = Some(value()?),
+                "--timeout" => {
+                    let timeout = value()?;
+                    let timeout = timeout
+                        .parse()
+                        .map_err(|_| CliError::Usage(format!("invalid timeout: {}", timeout)))?;
+                    options.timeout = Duration::from_millis(timeout);
+                }
+                "--no-color" => options.color = false,
+                _ if argument.starts_with('-') => {
+                    return Err(CliError::Usage(format!("unknown option: {}", argument)))
+                }
+                _ if device.is_none() => device = Some(argument),
+                _ => {
+                    return Err(CliError::Usage(format!(
+                        "unexpected argument: {}",
+                        argument
+                    )))
+                }
+            }
+        }
+
+        options.device = device.ok_or_else(|| CliError::Usage("no device given".into()))?;
+        Ok(Some(options))
+    }
+}
+
+/// The ways the terminal can fail
+#[derive(Debug)]
+enum CliError {
+    /// The command line arguments are wrong
+    Usage(String),
+    /// The device or a file gave an error
+    Io(io::Error),
+    /// A line of the script is wrong
+    Script { line: usize, reason: String },
+    /// A typed command can't be built
+    Command(String),
+    /// The device didn't finish the response in time
+    Timeout,
+    /// The device closed the connection
+    Closed,
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(reason) => write!(f, "{}", reason),
+            CliError::Io(error) => write!(f, "{}", error),
+            CliError::Script { line, reason } => write!(f, "script line {}: {}", line, reason),
+            CliError::Command(reason) => write!(f, "{}", reason),
+            CliError::Timeout => write!(f, "no response before the timeout"),
+            CliError::Closed => write!(f, "the device closed the connection"),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::Io(error)
+    }
+}
+
+/// Opens the device and starts reading from it
+fn open_device(device: &str) -> io::Result<Link> {
+    if let Some(address) = device.strip_prefix("tcp:") {
+        let stream = TcpStream::connect(address)?;
+        return Ok(Link::new(stream.try_clone()?, stream));
+    }
+
+    #[cfg(unix)]
+    if let Some(path) = device.strip_prefix("unix:") {
+        let stream = std::os::unix::net::UnixStream::connect(path)?;
+        return Ok(Link::new(stream.try_clone()?, stream));
+    }
+
+    let file = fs::OpenOptions::new().read(true).write(true).open(device)?;
+    Ok(Link::new(file.try_clone()?, file))
+}
+
+/// # Link
+/// A connection to the device whose reads give up after a short while
+///
+/// A thread does the blocking reads, so reading works the same for every kind of device.
+/// A read that gets nothing in time fails with [io::ErrorKind::WouldBlock].
+struct Link {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    /// What is left of the last chunk
+    chunk: Vec<u8>,
+    writer: Box<dyn Write + Send>,
+}
+
+impl Link {
+    fn new(mut reader: impl Read + Send + 'static, writer: impl Write + Send + 'static) -> Self {
+        let (sender, chunks) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut buffer = [0; 256];
+            loop {
+                let chunk = reader
+                    .read(&mut buffer)
+                    .map(|length| buffer[..length].to_vec());
+                let done = !matches!(&chunk, Ok(chunk) if !chunk.is_empty());
+                if sender.send(chunk).is_err() || done {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            chunks,
+            chunk: Vec::new(),
+            writer: Box::new(writer),
+        }
+    }
+}
+
+impl Read for Link {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.chunk.is_empty() {
+            self.chunk = match self.chunks.recv_timeout(POLL_INTERVAL) {
+                Ok(chunk) => chunk?,
+                Err(RecvTimeoutError::Timeout) => return Err(io::ErrorKind::WouldBlock.into()),
+                Err(RecvTimeoutError::Disconnected) => Vec::new(),
+            };
+        }
+
+        let length = buffer.len().min(self.chunk.len());
+        buffer[..length].copy_from_slice(&self.chunk[..length]);
+        self.chunk.drain(..length);
+        Ok(length)
+    }
+}
+
+impl Write for Link {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.writer.write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A script of commands with their expected responses
+#[derive(Debug, PartialEq, Eq)]
+struct Script {
+    steps: Vec<ScriptStep>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ScriptStep {
+    /// The line of the script the command is on
+    line: usize,
+    command: String,
+    /// The data to send after the prompt
+    payload: Option<String>,
+    /// The lines of the response, without the final result code
+    lines: Vec<String>,
+    /// The final result code, if the response is checked
+    result: Option<String>,
+}
+
+impl Script {
+    fn parse(text: &str) -> Result<Self, CliError> {
+        let mut steps: Vec<ScriptStep> = Vec::new();
+        let mut expected = Vec::new();
+
+        let error = |line, reason: &str| CliError::Script {
+            line,
+            reason: reason.into(),
+        };
+
+        for (index, text) in text.lines().enumerate() {
+            let line = index + 1;
+            let text = text.trim();
+
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+
+            if let Some(response) = text.strip_prefix('<') {
+                if steps.is_empty() {
+                    return Err(error(line, "a response must follow a command"));
+                }
+                expected.push((line, response.trim_start().to_string()));
+                continue;
+            }
+
+            if let Some(step) = steps.last_mut() {
+                close_step(step, &mut expected)?;
+            }
+
+            if let Some(payload) = text.strip_prefix('>') {
+                match steps.last_mut() {
+                    Some(step) if step.payload.is_none() && step.result.is_none() => {
+                        step.payload = Some(payload.trim_start().to_string());
+                    }
+                    _ => return Err(error(line, "the data must follow a command")),
+                }
+                continue;
+            }
+
+            build_command(text, &mut [0; BUFFER_SIZE]).map_err(|reason| CliError::Script {
+                line,
+                reason: reason.to_string(),
+            })?;
+            steps.push(ScriptStep {
+                line,
+                command: text.to_string(),
+                payload: None,
+                lines: Vec::new(),
+                result: None,
+            });
+        }
+
+        if let Some(step) = steps.last_mut() {
+            close_step(step, &mut expected)?;
+        }
+
+        Ok(Self { steps })
+    }
+}
+
+impl ScriptStep {
+    /// Returns true if the response may have lines without a name,
+    /// because the step expects one of those or doesn't check the response
+    fn expects_unnamed_lines(&self) -> bool {
+        self.result.is_none()
+            || self
+                .lines
+                .iter()
+                .any(|line| split_name(line.as_bytes()).is_none())
+    }
+}
+
+/// Puts the expected lines in the step, of which the last must be the final result code
+fn close_step(step: &mut ScriptStep, expected: &mut Vec<(usize, String)>) -> Result<(), CliError> {
+    let (line, result) = match expected.pop() {
+        Some(last) => last,
+        None => return Ok(()),
+    };
+
+    if FinalResult::parse_line(result.as_bytes()).is_none() {
+        return Err(CliError::Script {
+            line,
+            reason: "the last line of a response must be a final result code".into(),
+        });
+    }
+
+    step.lines = expected.drain(..).map(|(_, line)| line).collect();
+    step.result = Some(result);
+    Ok(())
+}
+
+/// Parses a typed command and builds it again with the [CommandBuilder].
+///
+/// The parameters are sent as they were typed, so the device gets the `0080` of `AT+QCFG="band",0,0080`.
+fn build_command<'b>(text: &str, buffer: &'b mut [u8]) -> Result<&'b [u8], CliError> {
+    let command = IncomingCommand::parse(text.as_bytes()).map_err(|error| {
+        CliError::Command(format!(
+            "invalid command at index {}: {:?}",
+            error.index, error.kind
+        ))
+    })?;
+    let name = command.name();
+
+    let result = match command.kind() {
+        CommandKind::Test(_) => CommandBuilder::create_test(buffer, true)
+            .named(name)
+            .finish(),
+        CommandKind::Query(_) => CommandBuilder::create_query(buffer, true)
+            .named(name)
+            .finish(),
+        CommandKind::Execute(_) => CommandBuilder::create_execute(buffer, true)
+            .named(name)
+            .finish(),
+        CommandKind::Set(_) => {
+            let mut builder = CommandBuilder::create_set(buffer, true).named(name);
+            for parameter in command.parameters() {
+                builder = builder.with_raw_parameter(parameter);
+            }
+            builder.finish()
+        }
+    };
+
+    result.map_err(|size| CliError::Command(format!("the command needs {} bytes", size)))
+}
+
+/// A parameter as the parser reads it
+#[derive(Debug, PartialEq, Eq)]
+enum Value<'a> {
+    Empty,
+    Int(i32),
+    String(&'a str),
+    RangeList(&'a [u8]),
+    Raw(&'a [u8]),
+}
+
+/// Reads a parameter with the first kind of the parser that takes all of it
+fn parse_value(parameter: &[u8]) -> Value<'_> {
+    if parameter.is_empty() {
+        return Value::Empty;
+    }
+
+    // The parser stops at the end of the value, so it must be followed by the line end
+    let mut line = parameter.to_vec();
+    line.extend_from_slice(b"\r\n");
+    let parser = || CommandParser::parse(&line);
+
+    if let Ok((value,)) = parser()
+        .expect_int_parameter()
+        .expect_identifier(b"\r\n")
+        .finish()
+    {
+        return Value::Int(value);
+    }
+    if parser()
+        .expect_string_parameter()
+        .expect_identifier(b"\r\n")
+        .finish()
+        .is_ok()
+    {
+        // The string is in the line copy, so take it from the parameter
+        let (value,) = CommandParser::parse(parameter)
+            .expect_string_parameter()
+            .finish()
+            .unwrap_or(("",));
+        return Value::String(value);
+    }
+    if parser()
+        .expect_range_list_parameter()
+        .expect_identifier(b"\r\n")
+        .finish()
+        .is_ok()
+    {
+        return Value::RangeList(parameter);
+    }
+
+    Value::Raw(parameter)
+}
+
+/// Splits the parameters of a response line, skipping the commas in strings and range lists
+fn split_parameters(text: &[u8]) -> Vec<&[u8]> {
+    let mut parameters = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (index, byte) in text.iter().enumerate() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match byte {
+            b'\\' if in_string => escaped = true,
+            b'"' => in_string = !in_string,
+            b'(' if !in_string => depth += 1,
+            b')' if !in_string => depth = depth.saturating_sub(1),
+            b',' if !in_string && depth == 0 => {
+                parameters.push(text[start..index].trim_ascii());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    parameters.push(text[start..].trim_ascii());
+    parameters
+}
+
+/// Splits a line like `+CSQ: 20,99` into its name and parameters
+fn split_name(line: &[u8]) -> Option<(&[u8], &[u8])> {
+    if !matches!(line.first(), Some(b'+' | b'^' | b'$' | b'%' | b'#' | b'*')) {
+        return None;
+    }
+
+    let colon = line.iter().position(|byte| *byte == b':')?;
+    Some((&line[..colon], &line[colon + 1..]))
+}
+
+/// The command that waits for its response
+struct Pending {
+    name: String,
+    /// Lines without a name belong to the response, and are not URCs
+    unnamed_lines: bool,
+    lines: Vec<Vec<u8>>,
+}
+
+/// The response to a command
+#[derive(Debug, PartialEq, Eq)]
+struct Completed {
+    lines: Vec<Vec<u8>>,
+    /// The final result code, like `OK`
+    result: Vec<u8>,
+}
+
+/// What happened while polling the device
+#[derive(Debug, PartialEq, Eq)]
+enum Event {
+    Nothing,
+    Prompt,
+    Completed(Completed),
+    Closed,
+}
+
+#[derive(Clone, Copy)]
+enum Style {
+    Echo,
+    Response,
+    Urc,
+    Ok,
+    Error,
+    Prompt,
+}
+
+impl Style {
+    fn color(self) -> &'static str {
+        match self {
+            Style::Echo => "\x1b[2m",
+            Style::Response => "\x1b[1m",
+            Style::Urc => "\x1b[1;33m",
+            Style::Ok => "\x1b[1;32m",
+            Style::Error => "\x1b[1;31m",
+            Style::Prompt => "\x1b[36m",
+        }
+    }
+}
+
+/// # Terminal
+/// Sends commands to the device and prints what it sends back
+struct Terminal<D, W> {
+    device: D,
+    digester: Digester<BUFFER_SIZE>,
+    /// The bytes that were read but didn't fit in the digester yet
+    chunk: Vec<u8>,
+    out: W,
+    color: bool,
+    pending: Option<Pending>,
+}
+
+impl<D: Read + Write, W: Write> Terminal<D, W> {
+    fn new(device: D, out: W, color: bool) -> Self {
+        Self {
+            device,
+            digester: Digester::new(),
+            chunk: Vec::new(),
+            out,
+            color,
+            pending: None,
+        }
+    }
+
+    /// Gives back the device and the output
+    fn release(self) -> (D, W) {
+        (self.device, self.out)
+    }
+
+    /// Builds the typed command and sends it.
+    /// With `unnamed_lines`, the lines without a name that come in are part of its response.
+    fn send(&mut self, text: &str, unnamed_lines: bool) -> Result<(), CliError> {
+        let mut buffer = [0; BUFFER_SIZE];
+        let command = build_command(text, &mut buffer)?;
+        let name = IncomingCommand::parse(command)
+            .map(|command| command.name().to_string())
+            .unwrap_or_default();
+
+        self.device.write_all(command)?;
+        self.device.flush()?;
+        self.pending = Some(Pending {
+            name,
+            unnamed_lines,
+            lines: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Sends the data after a prompt, ended with a Ctrl-Z
+    fn send_payload(&mut self, data: &str) -> Result<(), CliError> {
+        let mut buffer = [0; BUFFER_SIZE];
+        let payload = CommandBuilder::create_payload(&mut buffer)
+            .with_data(data)
+            .finish_with_ctrl_z()
+            .map_err(|error| match error {
+                PayloadError::BufferTooSmall(size) => {
+                    CliError::Command(format!("the data needs {} bytes", size))
+                }
+                PayloadError::ReservedByte { index } => CliError::Command(format!(
+                    "the data can't contain Ctrl-Z or ESC, found one at index {}",
+                    index
+                )),
+            })?;
+
+        self.device.write_all(payload)?;
+        self.device.flush()?;
+        Ok(())
+    }
+
+    /// Handles what the device sent, reading from it once if there's nothing left
+    fn poll(&mut self) -> Result<Event, CliError> {
+        loop {
+            while let Some(item) = self.digester.next_item() {
+                let event = handle_item(&mut self.out, self.color, &mut self.pending, item)?;
+                if event != Event::Nothing {
+                    return Ok(event);
+                }
+            }
+
+            if !self.chunk.is_empty() {
+                let length = self.digester.feed(&self.chunk);
+                self.chunk.drain(..length);
+                continue;
+            }
+
+            let mut buffer = [0; 256];
+            match self.device.read(&mut buffer) {
+                Ok(0) => return Ok(Event::Closed),
+                Ok(length) => self.chunk.extend_from_slice(&buffer[..length]),
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(Event::Nothing)
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    /// Waits for the response to the command that was sent
+    fn wait(
+        &mut self,
+        timeout: Duration,
+        mut payload: Option<&str>,
+    ) -> Result<Completed, CliError> {
+        let deadline = Instant::now() + timeout;
+
+        while Instant::now() < deadline {
+            match self.poll()? {
+                Event::Nothing => {}
+                Event::Prompt => {
+                    if let Some(data) = payload.take() {
+                        self.send_payload(data)?;
+                    }
+                }
+                Event::Completed(completed) => return Ok(completed),
+                Event::Closed => return Err(CliError::Closed),
+            }
+        }
+
+        self.pending = None;
+        Err(CliError::Timeout)
+    }
+
+    /// Sends the commands of the script and checks the responses. Returns false if one didn't match.
+    fn run_script(&mut self, script: &Script, timeout: Duration) -> Result<bool, CliError> {
+        let mut passed = true;
+
+        for step in &script.steps {
+            // What the device sent before the command doesn't belong to the response
+            loop {
+                match self.poll()? {
+                    Event::Nothing => break,
+                    Event::Closed => return Err(CliError::Closed),
+                    Event::Prompt | Event::Completed(_) => {}
+                }
+            }
+
+            self.print(Style::Prompt, &format!("> {}", step.command))?;
+            self.send(&step.command, step.expects_unnamed_lines())?;
+            let mismatch = match self.wait(timeout, step.payload.as_deref()) {
+                Ok(completed) => check_step(step, &completed),
+                Err(CliError::Timeout) => Some("no response before the timeout".into()),
+                Err(error) => return Err(error),
+            };
+
+            if let Some(mismatch) = mismatch {
+                self.print(
+                    Style::Error,
+                    &format!("script line {}: {}", step.line, mismatch),
+                )?;
+                passed = false;
+            }
+        }
+
+        if passed {
+            self.print(Style::Ok, "script passed")?;
+        } else {
+            self.print(Style::Error, "script failed")?;
+        }
+        Ok(passed)
+    }
+
+    /// Sends the lines typed on stdin and prints everything the device sends until stdin closes
+    fn run_interactive(&mut self, timeout: Duration) -> Result<(), CliError> {
+        let (sender, input) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                if line.map(|line| sender.send(line)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut prompted = false;
+        let mut sent_at = None;
+
+        loop {
+            match self.poll()? {
+                Event::Nothing => {}
+                Event::Prompt => prompted = true,
+                Event::Completed(_) => sent_at = None,
+                Event::Closed => return Err(CliError::Closed),
+            }
+
+            if sent_at.is_some_and(|sent_at: Instant| sent_at.elapsed() > timeout) {
+                sent_at = None;
+                self.pending = None;
+                self.print(Style::Error, "no response before the timeout")?;
+            }
+
+            let line = match input.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => continue,
+                // Wait for the last response before stopping
+                Err(TryRecvError::Disconnected) if sent_at.is_some() => continue,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            };
+
+            let result = if prompted {
+                self.send_payload(&line)
+            } else if line.trim().is_empty() {
+                continue;
+            } else {
+                self.send(line.trim(), true)
+            };
+            prompted = false;
+
+            match result {
+                Ok(()) => sent_at = Some(Instant::now()),
+                Err(CliError::Command(reason)) => self.print(Style::Error, &reason)?,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    fn print(&mut self, style: Style, text: &str) -> io::Result<()> {
+        print_styled(&mut self.out, self.color, style, text)
+    }
+}
+
+/// Prints the item and keeps track of the response to the pending command
+fn handle_item(
+    out: &mut impl Write,
+    color: bool,
+    pending: &mut Option<Pending>,
+    item: DigestItem<'_>,
+) -> Result<Event, CliError> {
+    match item {
+        DigestItem::Echo(echo) => {
+            print_styled(out, color, Style::Echo, &format!("echo {}", show(echo)))?;
+        }
+        DigestItem::Prompt => {
+            print_styled(out, color, Style::Prompt, "> (send the data)")?;
+            return Ok(Event::Prompt);
+        }
+        DigestItem::Line(line) => match pending {
+            Some(pending) if is_response(pending, line) => {
+                print_line(out, color, Style::Response, "", line)?;
+                pending.lines.push(line.to_vec());
+            }
+            _ => print_line(out, color, Style::Urc, "URC ", line)?,
+        },
+        DigestItem::FinalResult(result) => {
+            let mut buffer = [0; BUFFER_SIZE];
+            let code = ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+                .finish(result)
+                .map(|code| code.trim_ascii().to_vec())
+                .unwrap_or_default();
+
+            let style = if result.is_error() {
+                Style::Error
+            } else {
+                Style::Ok
+            };
+            print_styled(out, color, style, &show(&code))?;
+
+            if let Some(pending) = pending.take() {
+                return Ok(Event::Completed(Completed {
+                    lines: pending.lines,
+                    result: code,
+                }));
+            }
+        }
+        DigestItem::Overflow(data) => {
+            print_styled(
+                out,
+                color,
+                Style::Error,
+                &format!("line too long, dropped {} bytes", data.len()),
+            )?;
+        }
+    }
+
+    Ok(Event::Nothing)
+}
+
+/// Lines with the name of the command belong to it, and lines without a name if the command takes those.
+/// Lines with another name are URCs.
+fn is_response(pending: &Pending, line: &[u8]) -> bool {
+    match split_name(line) {
+        Some((name, _)) => name.eq_ignore_ascii_case(pending.name.as_bytes()),
+        None => pending.unnamed_lines,
+    }
+}
+
+/// Prints the line with its parameters one by one
+fn print_line(
+    out: &mut impl Write,
+    color: bool,
+    style: Style,
+    label: &str,
+    line: &[u8],
+) -> io::Result<()> {
+    let (name, parameters) = match split_name(line) {
+        Some(split) => split,
+        None => return print_styled(out, color, style, &format!("{}{}", label, show(line))),
+    };
+
+    print_styled(out, color, style, &format!("{}{}:", label, show(name)))?;
+    for (index, parameter) in split_parameters(parameters).into_iter().enumerate() {
+        let value = match parse_value(parameter) {
+            Value::Empty => "(empty)".to_string(),
+            Value::Int(value) => format!("{} (int)", value),
+            Value::String(value) => format!("{:?} (string)", value),
+            Value::RangeList(value) => format!("{} (range list)", show(value)),
+            Value::Raw(value) => show(value),
+        };
+        writeln!(out, "    {}: {}", index + 1, value)?;
+    }
+
+    Ok(())
+}
+
+fn print_styled(out: &mut impl Write, color: bool, style: Style, text: &str) -> io::Result<()> {
+    if color {
+        writeln!(out, "{}{}\x1b[0m", style.color(), text)
+    } else {
+        writeln!(out, "{}", text)
+    }
+}
+
+/// Shows the bytes as text, escaping the bytes that aren't printable
+fn show(bytes: &[u8]) -> String {
+    bytes.escape_ascii().to_string()
+}
+
+/// Compares the response to what the script expects. Returns what is different.
+fn check_step(step: &ScriptStep, completed: &Completed) -> Option<String> {
+    let expected_result = step.result.as_ref()?;
+
+    if FinalResult::parse_line(expected_result.as_bytes())
+        != FinalResult::parse_line(&completed.result)
+    {
+        return Some(format!(
+            "expected {} but got {}",
+            expected_result,
+            show(&completed.result)
+        ));
+    }
+
+    if step.lines.len() != completed.lines.len() {
+        return Some(format!(
+            "expected {} lines but got {}",
+            step.lines.len(),
+            completed.lines.len()
+        ));
+    }
+
+    step.lines
+        .iter()
+        .zip(&completed.lines)
+        .find(|(expected, line)| expected.as_bytes() != line.as_slice())
+        .map(|(expected, line)| format!("expected {:?} but got {:?}", expected, show(line)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::net::TcpListener;
+
+    /// A modem that answers every command it knows with a fixed response
+    struct FakeModem {
+        answers: Vec<(&'static [u8], &'static [u8])>,
+        written: Vec<u8>,
+        readable: VecDeque<u8>,
+    }
+
+    impl FakeModem {
+        fn new(answers: Vec<(&'static [u8], &'static [u8])>) -> Self {
+            Self {
+                answers,
+                written: Vec::new(),
+                readable: VecDeque::new(),
+            }
+        }
+    }
+
+    impl Read for FakeModem {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if self.readable.is_empty() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            let length = buffer.len().min(self.readable.len()).min(7);
+            for byte in &mut buffer[..length] {
+                *byte = self.readable.pop_front().unwrap_or_default();
+            }
+            Ok(length)
+        }
+    }
+
+    impl Write for FakeModem {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buffer);
+            let answer = self
+                .answers
+                .iter()
+                .find(|(command, _)| *command == buffer)
+                .map(|(_, answer)| *answer);
+            if let Some(answer) = answer {
+                self.readable.extend(answer);
+            }
+            Ok(buffer.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_build_command() {
+        let mut buffer = [0; 64];
+        for (text, expected) in [
+            ("AT", &b"AT\r\n"[..]),
+            ("ATE0", b"ATE0\r\n"),
+            ("AT+CSQ", b"AT+CSQ\r\n"),
+            ("AT+CFUN?", b"AT+CFUN?\r\n"),
+            ("AT+CFUN=?", b"AT+CFUN=?\r\n"),
+            (
+                "AT+CGDCONT=1,\"IP\",\"internet\"",
+                b"AT+CGDCONT=1,\"IP\",\"internet\"\r\n",
+            ),
+            ("AT+COPS=1,,\"x\"", b"AT+COPS=1,,\"x\"\r\n"),
+            ("AT+QCFG=band,0x10", b"AT+QCFG=band,0x10\r\n"),
+            ("AT+QCFG=\"band\",0,0080", b"AT+QCFG=\"band\",0,0080\r\n"),
+            ("AT+X=007,+5", b"AT+X=007,+5\r\n"),
+            ("AT+X=\"a\\\"b\"", b"AT+X=\"a\\\"b\"\r\n"),
+        ] {
+            assert_eq!(
+                build_command(text, &mut buffer).unwrap(),
+                expected,
+                "{}",
+                text
+            );
+        }
+
+        assert!(matches!(
+            build_command("hello", &mut buffer),
+            Err(CliError::Command(_))
+        ));
+        assert!(matches!(
+            build_command("AT+CGMR", &mut [0; 4]),
+            Err(CliError::Command(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value(b""), Value::Empty);
+        assert_eq!(parse_value(b"-20"), Value::Int(-20));
+        assert_eq!(parse_value(b"\"IP\""), Value::String("IP"));
+        assert_eq!(parse_value(b"(0-3,5)"), Value::RangeList(b"(0-3,5)"));
+        assert_eq!(parse_value(b"1.0"), Value::Raw(b"1.0"));
+        assert_eq!(parse_value(b"\"a\"b"), Value::Raw(b"\"a\"b"));
+
+        assert_eq!(
+            split_parameters(b" (0-1),\"a,b\", 3"),
+            [&b"(0-1)"[..], b"\"a,b\"", b"3"]
+        );
+        assert_eq!(
+            split_parameters(b"\"a\\\",b\",\"c\\\\\",1"),
+            [&b"\"a\\\",b\""[..], b"\"c\\\\\"", b"1"]
+        );
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let script = Script::parse(
+            "# Comment\n\nAT+CSQ\n< +CSQ: 20,99\n< OK\nAT+CMGS=5\n> hello\n< +CMGS: 1\n<   OK\n  # Indented comment\nAT\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            script.steps,
+            [
+                ScriptStep {
+                    line: 3,
+                    command: "AT+CSQ".into(),
+                    payload: None,
+                    lines: vec!["+CSQ: 20,99".into()],
+                    result: Some("OK".into()),
+                },
+                ScriptStep {
+                    line: 6,
+                    command: "AT+CMGS=5".into(),
+                    payload: Some("hello".into()),
+                    lines: vec!["+CMGS: 1".into()],
+                    result: Some("OK".into()),
+                },
+                ScriptStep {
+                    line: 11,
+                    command: "AT".into(),
+                    payload: None,
+                    lines: vec![],
+                    result: None,
+                },
+            ]
+        );
+
+        for (text, line) in [
+            ("< OK", 1),
+            ("AT\n< +CSQ: 1", 2),
+            ("hello", 1),
+            ("AT\n< OK\n> data", 3),
+        ] {
+            assert!(
+                matches!(Script::parse(text), Err(CliError::Script { line: l, .. }) if l == line),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_script() {
+        let modem = FakeModem::new(vec![
+            (
+                b"AT+CSQ\r\n",
+                b"AT+CSQ\r\r\n+CSQ: 20,99\r\n\r\n+CREG: 1\r\n\r\nOK\r\n",
+            ),
+            (b"AT+CMGS=5\r\n", b"\r\n> "),
+            (b"hello\x1A", b"\r\n+CMGS: 7\r\n\r\nOK\r\n"),
+            (b"AT+CPIN?\r\n", b"\r\n+CME ERROR: 10\r\n"),
+            (b"AT+COPS?\r\n", b"\r\n+COPS: 0\r\n\r\nRING\r\n\r\nOK\r\n"),
+            (b"AT+CGMR\r\n", b"\r\n1.0\r\n\r\nOK\r\n"),
+        ]);
+        let mut terminal = Terminal::new(Recorder::new(modem), Vec::new(), false);
+
+        let script = Script::parse(
+            "AT+CSQ\n< +CSQ: 20,99\n< OK\nAT+CMGS=5\n> hello\n< +CMGS: 7\n< OK\nAT+CPIN?\n< +CME ERROR: 10\n\
+             AT+COPS?\n< +COPS: 0\n< OK\nAT+CGMR\n< 1.0\n< OK\n",
+        )
+        .unwrap();
+        assert!(terminal
+            .run_script(&script, Duration::from_millis(100))
+            .unwrap());
+
+        let script = Script::parse("AT+CPIN?\n< OK\n").unwrap();
+        assert!(!terminal
+            .run_script(&script, Duration::from_millis(100))
+            .unwrap());
+
+        // Nothing answers the first one, which doesn't stop the script
+        let script = Script::parse("AT+CGSN\nAT+CPIN?\n< +CME ERROR: 10\n").unwrap();
+        assert!(!terminal
+            .run_script(&script, Duration::from_millis(10))
+            .unwrap());
+
+        let (device, out) = terminal.release();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("echo AT+CSQ\n"));
+        assert!(out.contains("+CSQ:\n    1: 20 (int)\n    2: 99 (int)\n"));
+        assert!(out.contains("URC +CREG:\n    1: 1 (int)\n"));
+        assert!(out.contains("URC RING\n"));
+        assert!(out.contains("> AT+CGMR\n1.0\nOK\nscript passed\n"));
+        assert!(out.contains("script line 1: expected OK but got +CME ERROR: 10\nscript failed\n"));
+        assert!(out.contains(
+            "script line 1: no response before the timeout\n> AT+CPIN?\n+CME ERROR: 10\nscript failed\n"
+        ));
+
+        let (_, transcript) = device.finish();
+        assert!(transcript.to_string().contains("TX \"hello\\x1A\""));
+    }
+
+    #[test]
+    fn test_tcp_device() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let modem = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 64];
+            let mut received = Vec::new();
+            while !received.ends_with(b"\r\n") {
+                let length = stream.read(&mut buffer).unwrap();
+                received.extend_from_slice(&buffer[..length]);
+            }
+            stream.write_all(b"\r\n+CGMR: 1.0\r\n\r\nOK\r\n").unwrap();
+            received
+        });
+
+        let device = open_device(&format!("tcp:{}", address)).unwrap();
+        let mut terminal = Terminal::new(device, Vec::new(), true);
+        let script = Script::parse("AT+CGMR\n< +CGMR: 1.0\n< OK\n").unwrap();
+        assert!(terminal
+            .run_script(&script, Duration::from_secs(5))
+            .unwrap());
+
+        assert_eq!(modem.join().unwrap(), b"AT+CGMR\r\n");
+        let (_, out) = terminal.release();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .contains("\x1b[1;32mOK\x1b[0m"));
+    }
+
+    #[test]
+    fn test_record_on_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        // A modem that never answers, it hangs up once the test is done
+        let (hang_up, hung_up) = mpsc::channel::<()>();
+        let modem = thread::spawn(move || {
+            let (_stream, _) = listener.accept().unwrap();
+            hung_up.recv().ok();
+        });
+
+        let directory = std::env::temp_dir().join(format!("at-cli-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let script = directory.join("script.txt");
+        let record = directory.join("record.txt");
+        fs::write(&script, "AT+CGMR\n< OK\n").unwrap();
+
+        let options = Options {
+            device: format!("tcp:{}", address),
+            script: Some(script.to_string_lossy().into()),
+            record: Some(record.to_string_lossy().into()),
+            timeout: Duration::from_millis(50),
+            color: false,
+        };
+        assert!(matches!(run(&options), Ok(false)));
+
+        let transcript = fs::read_to_string(&record).unwrap();
+        assert!(
+            transcript.contains("TX \"AT+CGMR\\r\\n\""),
+            "{}",
+            transcript
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+        hang_up.send(()).unwrap();
+        modem.join().unwrap();
+    }
+
+    #[test]
+    fn test_options() {
+        let arguments = |text: &str| text.split(' ').map(String::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            Options::parse(arguments(
+                "--script a.txt --timeout 100 --no-color tcp:localhost:1"
+            ))
+            .unwrap(),
+            Some(Options {
+                device: "tcp:localhost:1".into(),
+                script: Some("a.txt".into()),
+                record: None,
+                timeout: Duration::from_millis(100),
+                color: false,
+            })
+        );
+        assert_eq!(Options::parse(arguments("-h")).unwrap(), None);
+        assert!(Options::parse(arguments("--timeout")).is_err());
+        assert!(Options::parse(arguments("--timeout x /dev/tty")).is_err());
+        assert!(Options::parse(arguments("a b")).is_err());
+        assert!(Options::parse(Vec::new()).is_err());
+    }
+}
@@ -1,5 +1,8 @@
 //! Implementation of the CommandBuilder
 
+use crate::escape::Escaping;
+use crate::Integer;
+
 /// # CommandBuilder
 /// A builder struct for AT Commands
 ///
@@ -121,36 +124,203 @@ impl<'a> CommandBuilder<'a, Uninitialized> {
 
         builder
     }
+
+    /// Creates a builder for a basic command in the form `AT{name}` or `AT{name}{value}`, like `ATE0` or `ATZ`.
+    ///
+    /// The given buffer is used to build the command in and must be big enough to contain it.
+    ///
+    /// ```rust
+    /// use at_commands::builder::CommandBuilder;
+    ///
+    /// let mut buffer = [0; 128];
+    ///
+    /// let result = CommandBuilder::create_basic(&mut buffer, true)
+    ///     .named("E")
+    ///     .with_value(0)
+    ///     .chain_basic()
+    ///     .named("V")
+    ///     .with_value(1)
+    ///     .chain_ampersand()
+    ///     .named("W")
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(result, b"ATE0V1&W\r\n");
+    /// ```
+    pub fn create_basic(
+        buffer: &'a mut [u8],
+        at_prefix: bool,
+    ) -> CommandBuilder<'a, Initialized<Basic>> {
+        let mut builder = CommandBuilder::<'a, Initialized<Basic>> {
+            buffer,
+            index: 0,
+            phantom: Default::default(),
+        };
+
+        if at_prefix {
+            builder.try_append_data(b"AT");
+        }
+
+        builder
+    }
+
+    /// Creates a builder for an ampersand command in the form `AT&{name}` or `AT&{name}{value}`, like `AT&F` or `AT&D2`.
+    ///
+    /// The given buffer is used to build the command in and must be big enough to contain it.
+    pub fn create_ampersand(
+        buffer: &'a mut [u8],
+        at_prefix: bool,
+    ) -> CommandBuilder<'a, Initialized<Ampersand>> {
+        let mut builder = CommandBuilder::<'a, Initialized<Ampersand>> {
+            buffer,
+            index: 0,
+            phantom: Default::default(),
+        };
+
+        if at_prefix {
+            builder.try_append_data(b"AT");
+        }
+
+        builder
+    }
+
+    /// Creates a builder for setting or querying an S-register, like `ATS0=2` or `ATS3?`.
+    ///
+    /// The given buffer is used to build the command in and must be big enough to contain it.
+    ///
+    /// ```rust
+    /// use at_commands::builder::CommandBuilder;
+    ///
+    /// let mut buffer = [0; 128];
+    ///
+    /// let result = CommandBuilder::create_s_register(&mut buffer, true)
+    ///     .set(0, 2)
+    ///     .chain_s_register()
+    ///     .query(3)
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(result, b"ATS0=2S3?\r\n");
+    /// ```
+    pub fn create_s_register(
+        buffer: &'a mut [u8],
+        at_prefix: bool,
+    ) -> CommandBuilder<'a, Initialized<SRegister>> {
+        let mut builder = CommandBuilder::<'a, Initialized<SRegister>> {
+            buffer,
+            index: 0,
+            phantom: Default::default(),
+        };
+
+        if at_prefix {
+            builder.try_append_data(b"AT");
+        }
+
+        builder
+    }
+
+    /// Creates a builder for a dial command, like `ATD*99#` or `ATD>SM1;`.
+    ///
+    /// The given buffer is used to build the command in and must be big enough to contain it.
+    ///
+    /// ```rust
+    /// use at_commands::builder::CommandBuilder;
+    ///
+    /// let mut buffer = [0; 128];
+    ///
+    /// // Start a data call to go into PPP
+    /// let result = CommandBuilder::create_dial(&mut buffer, true)
+    ///     .dial_string("*99#")
+    ///     .unwrap()
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(result, b"ATD*99#\r\n");
+    ///
+    /// // Start a voice call to the first entry of the SIM phonebook
+    /// let result = CommandBuilder::create_dial(&mut buffer, true)
+    ///     .phonebook_entry(Some("SM"), 1)
+    ///     .unwrap()
+    ///     .voice()
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(result, b"ATD>SM1;\r\n");
+    /// ```
+    pub fn create_dial(
+        buffer: &'a mut [u8],
+        at_prefix: bool,
+    ) -> CommandBuilder<'a, Initialized<Dial>> {
+        let mut builder = CommandBuilder::<'a, Initialized<Dial>> {
+            buffer,
+            index: 0,
+            phantom: Default::default(),
+        };
+
+        if at_prefix {
+            builder.try_append_data(b"AT");
+        }
+
+        builder
+    }
+
+    /// Creates a builder for the payload that is sent after the device gave the `>` prompt.
+    ///
+    /// The given buffer is used to build the payload in and must be big enough to contain it.
+    ///
+    /// ```rust
+    /// use at_commands::builder::{CommandBuilder, CTRL_Z};
+    ///
+    /// let mut buffer = [0; 128];
+    ///
+    /// // A text message of unknown length ends with Ctrl-Z
+    /// let result = CommandBuilder::create_payload(&mut buffer)
+    ///     .with_data("Hello world")
+    ///     .finish_with_ctrl_z()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(result, b"Hello world\x1A");
+    ///
+    /// // The length of the data was already given with `AT+CIPSEND=0,5`
+    /// let result = CommandBuilder::create_payload(&mut buffer)
+    ///     .with_data([0, 1, 2, 3, 4])
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(result, [0, 1, 2, 3, 4]);
+    /// ```
+    pub fn create_payload(buffer: &'a mut [u8]) -> CommandBuilder<'a, Payload> {
+        CommandBuilder::<'a, Payload> {
+            buffer,
+            index: 0,
+            phantom: Default::default(),
+        }
+    }
 }
+
 impl<'a, ANY> CommandBuilder<'a, ANY> {
     /// Tries to append data to the buffer.
     ///
     /// If it won't fit, it silently fails and won't copy the data.
     /// The index field is incremented no matter what.
     fn try_append_data(&mut self, data: &[u8]) {
-        let data_length = data.len();
-
-        // Why not just use copy_from_slice?
-        // That can give a panic and thus dumps a lot of fmt code in the binary.
-        // The compiler can check every aspect of this and so the code will never panic.
-
-        // Does the buffer have enough space left?
-        if let Some(buffer_slice) = self.buffer.get_mut(self.index..(self.index + data_length)) {
-            // Yes, zip the buffer with the data
-            for (buffer, data) in buffer_slice.iter_mut().zip(data) {
-                // Copy over the bytes.
-                *buffer = *data;
-            }
-        }
+        self.index = crate::formatter::try_append_data(self.buffer, self.index, data);
+    }
 
-        // Increment the index
-        self.index += data_length;
+    /// Gives the built data, or the required size if the buffer was too small.
+    fn built(self) -> Result<&'a [u8], usize> {
+        if self.index > self.buffer.len() {
+            Err(self.index)
+        } else {
+            Ok(&self.buffer[0..self.index])
+        }
     }
 }
 
 impl<'a, N: Nameable> CommandBuilder<'a, Initialized<N>> {
     /// Set the name of the command.
     pub fn named<T: AsRef<[u8]>>(mut self, name: T) -> CommandBuilder<'a, N> {
+        self.try_append_data(N::NAME_PREFIX);
         self.try_append_data(name.as_ref());
         self.try_append_data(N::NAME_SUFFIX);
 
@@ -162,22 +332,299 @@ impl<'a, N: Nameable> CommandBuilder<'a, Initialized<N>> {
     }
 }
 
-impl<'a> CommandBuilder<'a, Set> {
-    /// Add an integer parameter.
-    pub fn with_int_parameter<INT: Into<i32>>(mut self, value: INT) -> Self {
+impl<'a> CommandBuilder<'a, Initialized<SRegister>> {
+    /// Queries the value of the S-register, like `S3?`.
+    pub fn query(mut self, register: u8) -> CommandBuilder<'a, Complete> {
+        self.try_append_register(register);
+        self.try_append_data(b"?");
+
+        CommandBuilder::<'a, Complete> {
+            buffer: self.buffer,
+            index: self.index,
+            phantom: Default::default(),
+        }
+    }
+
+    /// Sets the value of the S-register, like `S0=2`.
+    pub fn set(mut self, register: u8, value: u8) -> CommandBuilder<'a, Complete> {
+        self.try_append_register(register);
+        self.try_append_data(b"=");
+        let mut formatting_buffer = [0; crate::formatter::MAX_INT_DIGITS];
+        self.try_append_data(crate::formatter::write_int(&mut formatting_buffer, value));
+
+        CommandBuilder::<'a, Complete> {
+            buffer: self.buffer,
+            index: self.index,
+            phantom: Default::default(),
+        }
+    }
+
+    fn try_append_register(&mut self, register: u8) {
         let mut formatting_buffer = [0; crate::formatter::MAX_INT_DIGITS];
+        self.try_append_data(b"S");
         self.try_append_data(crate::formatter::write_int(
             &mut formatting_buffer,
-            value.into(),
+            register,
         ));
+    }
+}
+
+impl<'a> CommandBuilder<'a, Initialized<Dial>> {
+    /// Dials the dial string, like `+31612345678` or `*99#`.
+    ///
+    /// The dial string can contain the digits, `*`, `#`, `+` and `A` to `D`,
+    /// the V.250 modifiers `,` (pause), `T`, `P`, `!` (flash), `W` (wait for dial tone) and `@` (wait for quiet answer),
+    /// and the 3GPP modifiers `I`, `i`, `G` and `g`.
+    /// Any other character is an error that points at it.
+    pub fn dial_string<T: AsRef<[u8]>>(
+        mut self,
+        dial_string: T,
+    ) -> Result<CommandBuilder<'a, Dial>, InvalidDialString> {
+        let dial_string = dial_string.as_ref();
+
+        if let Some(index) = dial_string
+            .iter()
+            .position(|character| !is_dial_character(*character))
+        {
+            return Err(InvalidDialString { index });
+        }
+
+        self.try_append_data(b"D");
+        self.try_append_data(dial_string);
+
+        Ok(CommandBuilder::<'a, Dial> {
+            buffer: self.buffer,
+            index: self.index,
+            phantom: Default::default(),
+        })
+    }
+
+    /// Dials an entry of a phonebook, like `>SM1` or `>1`.
+    ///
+    /// The memory, like `SM` or `ME`, can only contain letters and digits.
+    /// Without a memory, the entry is from the phonebook that is selected with `+CPBS`.
+    pub fn phonebook_entry(
+        mut self,
+        memory: Option<&str>,
+        entry: u16,
+    ) -> Result<CommandBuilder<'a, Dial>, InvalidDialString> {
+        let memory = memory.unwrap_or("").as_bytes();
+
+        if let Some(index) = memory
+            .iter()
+            .position(|character| !character.is_ascii_alphanumeric())
+        {
+            return Err(InvalidDialString { index });
+        }
+
+        let mut formatting_buffer = [0; crate::formatter::MAX_INT_DIGITS];
+        self.try_append_data(b"D>");
+        self.try_append_data(memory);
+        self.try_append_data(crate::formatter::write_int(&mut formatting_buffer, entry));
+
+        Ok(CommandBuilder::<'a, Dial> {
+            buffer: self.buffer,
+            index: self.index,
+            phantom: Default::default(),
+        })
+    }
+}
+
+impl<'a> CommandBuilder<'a, Dial> {
+    /// Makes it a voice call by adding the `;` suffix.
+    ///
+    /// The device stays in command mode, so other commands can follow on the same line.
+    pub fn voice(mut self) -> CommandBuilder<'a, Complete> {
+        self.try_append_data(b";");
+
+        CommandBuilder::<'a, Complete> {
+            buffer: self.buffer,
+            index: self.index,
+            phantom: Default::default(),
+        }
+    }
+
+    /// Finishes the builder.
+    ///
+    /// The device leaves command mode after a dial command without the voice suffix,
+    /// so no other command can follow it on the same line.
+    ///
+    /// When Ok, it returns the slice of the buffer that contains the command.
+    /// If the buffer was not long enough, then an Err is returned with the size that was required for it to succeed.
+    pub fn finish(self) -> Result<&'a [u8], usize> {
+        self.finish_with(b"\r\n")
+    }
+
+    /// Finishes the builder with the given terminator.
+    ///
+    /// See [Self::finish] for the return value.
+    pub fn finish_with(mut self, terminator: &[u8]) -> Result<&'a [u8], usize> {
+        // A trailing comma is a pause and is kept
+        self.try_append_data(terminator);
+        self.built()
+    }
+}
+
+/// Returns true for the characters of V.250 and 3GPP 27.007 that can be in a dial string
+fn is_dial_character(character: u8) -> bool {
+    matches!(
+        character,
+        b'0'..=b'9'
+            | b'*'
+            | b'#'
+            | b'+'
+            | b'A'..=b'D'
+            | b'a'..=b'd'
+            | b','
+            | b'T'
+            | b't'
+            | b'P'
+            | b'p'
+            | b'!'
+            | b'W'
+            | b'w'
+            | b'@'
+            | b'I'
+            | b'i'
+            | b'G'
+            | b'g'
+    )
+}
+
+/// Error for a dial string with a character that is not allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidDialString {
+    /// The index of the character that is not allowed
+    pub index: usize,
+}
+
+/// Error for a payload that can't be finished with Ctrl-Z
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PayloadError {
+    /// The buffer was not long enough. It has the size that was required for it to succeed.
+    BufferTooSmall(usize),
+    /// The payload contains a [CTRL_Z] or [ESC] byte that would end it early
+    ReservedByte {
+        /// The index of the byte in the payload
+        index: usize,
+    },
+}
+
+impl<'a, B: BasicValue> CommandBuilder<'a, B> {
+    /// Add the numeric value of a basic command, like the `0` of `E0`.
+    ///
+    /// The value can't be negative, because `E-1` is not a valid command.
+    pub fn with_value(mut self, value: u32) -> CommandBuilder<'a, Complete> {
+        let mut formatting_buffer = [0; crate::formatter::MAX_INT_DIGITS];
+        self.try_append_data(crate::formatter::write_int(&mut formatting_buffer, value));
+
+        CommandBuilder::<'a, Complete> {
+            buffer: self.buffer,
+            index: self.index,
+            phantom: Default::default(),
+        }
+    }
+}
+
+impl<'a> CommandBuilder<'a, Set> {
+    /// Add an integer parameter.
+    pub fn with_int_parameter<INT: Into<i32>>(self, value: INT) -> Self {
+        self.with_int_parameter_as(value.into())
+    }
+
+    /// Add an integer parameter of any [Integer] type, like a `u32` or `i64`.
+    pub fn with_int_parameter_as<INT: Integer>(mut self, value: INT) -> Self {
+        let mut formatting_buffer = [0; crate::formatter::MAX_INT_DIGITS];
+        self.try_append_data(crate::formatter::write_int(&mut formatting_buffer, value));
+        self.try_append_data(b",");
+        self
+    }
+
+    /// Add an integer parameter as hex digits, like `80084`.
+    ///
+    /// The digits are padded with zeros up to the width.
+    /// Signed values are written as their two's complement, so `-1i8` is `FF`.
+    pub fn with_hex_parameter<INT: Integer>(
+        mut self,
+        value: INT,
+        width: usize,
+        case: HexCase,
+    ) -> Self {
+        self.try_append_hex(value, width, case);
         self.try_append_data(b",");
         self
     }
 
+    /// Add an integer parameter as hex digits between quotes, like `"1A2B"`.
+    ///
+    /// See [Self::with_hex_parameter] for the formatting of the digits.
+    pub fn with_quoted_hex_parameter<INT: Integer>(
+        mut self,
+        value: INT,
+        width: usize,
+        case: HexCase,
+    ) -> Self {
+        self.try_append_data(b"\"");
+        self.try_append_hex(value, width, case);
+        self.try_append_data(b"\"");
+        self.try_append_data(b",");
+        self
+    }
+
+    /// Add bytes as a parameter of hex digits, like `A0B1C2`.
+    ///
+    /// Every byte is written as two uppercase hex digits.
+    pub fn with_hex_bytes_parameter<T: AsRef<[u8]>>(mut self, value: T) -> Self {
+        self.try_append_hex_bytes(value.as_ref());
+        self.try_append_data(b",");
+        self
+    }
+
+    /// Add bytes as a parameter of hex digits between quotes, like `"A0B1C2"`.
+    ///
+    /// Every byte is written as two uppercase hex digits.
+    pub fn with_quoted_hex_bytes_parameter<T: AsRef<[u8]>>(mut self, value: T) -> Self {
+        self.try_append_data(b"\"");
+        self.try_append_hex_bytes(value.as_ref());
+        self.try_append_data(b"\"");
+        self.try_append_data(b",");
+        self
+    }
+
+    fn try_append_hex_bytes(&mut self, value: &[u8]) {
+        for byte in value {
+            self.try_append_data(&crate::formatter::hex_byte(*byte, true));
+        }
+    }
+
+    fn try_append_hex<INT: Integer>(&mut self, value: INT, width: usize, case: HexCase) {
+        let mut formatting_buffer = [0; crate::formatter::MAX_HEX_DIGITS];
+        let digits =
+            crate::formatter::write_hex(&mut formatting_buffer, value, case == HexCase::Upper);
+
+        for _ in digits.len()..width {
+            self.try_append_data(b"0");
+        }
+        self.try_append_data(digits);
+    }
+
     /// Add a string parameter
-    pub fn with_string_parameter<T: AsRef<[u8]>>(mut self, value: T) -> Self {
+    pub fn with_string_parameter<T: AsRef<[u8]>>(self, value: T) -> Self {
+        self.with_escaped_string_parameter(value, Escaping::None)
+    }
+
+    /// Add a string parameter in which the special characters are escaped.
+    ///
+    /// See [Escaping] for which characters get escaped and how.
+    pub fn with_escaped_string_parameter<T: AsRef<[u8]>>(
+        mut self,
+        value: T,
+        escaping: Escaping,
+    ) -> Self {
         self.try_append_data(b"\"");
-        self.try_append_data(value.as_ref());
+        escaping.escape(value.as_ref(), |data| self.try_append_data(data));
         self.try_append_data(b"\"");
         self.try_append_data(b",");
         self
@@ -185,9 +632,14 @@ impl<'a> CommandBuilder<'a, Set> {
 
     /// Add an optional integer parameter.
     pub fn with_optional_int_parameter<INT: Into<i32>>(self, value: Option<INT>) -> Self {
+        self.with_optional_int_parameter_as(value.map(Into::into))
+    }
+
+    /// Add an optional integer parameter of any [Integer] type.
+    pub fn with_optional_int_parameter_as<INT: Integer>(self, value: Option<INT>) -> Self {
         match value {
             None => self.with_empty_parameter(),
-            Some(value) => self.with_int_parameter(value),
+            Some(value) => self.with_int_parameter_as(value),
         }
     }
 
@@ -213,6 +665,144 @@ impl<'a> CommandBuilder<'a, Set> {
     }
 }
 
+impl<'a, F: Finishable> CommandBuilder<'a, F> {
+    /// Ends the current command so another command can follow on the same line.
+    ///
+    /// Extended commands end with a `;`, basic commands don't need a separator.
+    fn chain<NEXT>(mut self) -> CommandBuilder<'a, Initialized<NEXT>> {
+        // if last byte is a comma, decrement index to drop it
+        if let Some(b',') = self.buffer.get(self.index.wrapping_sub(1)) {
+            self.index -= 1;
+        }
+        self.try_append_data(F::CHAIN_SEPARATOR);
+
+        CommandBuilder::<'a, Initialized<NEXT>> {
+            buffer: self.buffer,
+            index: self.index,
+            phantom: Default::default(),
+        }
+    }
+
+    /// Adds a test command to the same line, like `AT+A;+B=?`.
+    ///
+    /// The commands are separated with a `;` and share the `AT` prefix.
+    ///
+    /// ```rust
+    /// use at_commands::builder::CommandBuilder;
+    ///
+    /// let mut buffer = [0; 128];
+    ///
+    /// let result = CommandBuilder::create_set(&mut buffer, true)
+    ///     .named("+CMEE")
+    ///     .with_int_parameter(2)
+    ///     .chain_set()
+    ///     .named("+CREG")
+    ///     .with_int_parameter(2)
+    ///     .chain_query()
+    ///     .named("+CSQ")
+    ///     .chain_execute()
+    ///     .named("E0")
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(result, b"AT+CMEE=2;+CREG=2;+CSQ?;E0\r\n");
+    /// ```
+    pub fn chain_test(self) -> CommandBuilder<'a, Initialized<Test>> {
+        self.chain()
+    }
+
+    /// Adds a query command to the same line, like `AT+A;+B?`.
+    ///
+    /// See [Self::chain_test].
+    pub fn chain_query(self) -> CommandBuilder<'a, Initialized<Query>> {
+        self.chain()
+    }
+
+    /// Adds a set command to the same line, like `AT+A;+B=1`.
+    ///
+    /// See [Self::chain_test].
+    pub fn chain_set(self) -> CommandBuilder<'a, Initialized<Set>> {
+        self.chain()
+    }
+
+    /// Adds an execute command to the same line, like `AT+A;+B`.
+    ///
+    /// See [Self::chain_test].
+    pub fn chain_execute(self) -> CommandBuilder<'a, Initialized<Execute>> {
+        self.chain()
+    }
+
+    /// Adds a basic command to the same line, like `AT+A;E0`.
+    ///
+    /// See [Self::chain_test].
+    pub fn chain_basic(self) -> CommandBuilder<'a, Initialized<Basic>> {
+        self.chain()
+    }
+
+    /// Adds an ampersand command to the same line, like `ATZ&F`.
+    ///
+    /// See [Self::chain_test].
+    pub fn chain_ampersand(self) -> CommandBuilder<'a, Initialized<Ampersand>> {
+        self.chain()
+    }
+
+    /// Adds an S-register command to the same line, like `ATE0S0=2`.
+    ///
+    /// See [Self::chain_test].
+    pub fn chain_s_register(self) -> CommandBuilder<'a, Initialized<SRegister>> {
+        self.chain()
+    }
+}
+
+impl<'a> CommandBuilder<'a, Payload> {
+    /// Add data to the payload.
+    ///
+    /// A payload that ends with Ctrl-Z can't contain the [CTRL_Z] and [ESC] bytes,
+    /// because the device would take them as the end of the payload.
+    /// [Self::finish_with_ctrl_z] rejects them.
+    pub fn with_data<T: AsRef<[u8]>>(mut self, data: T) -> Self {
+        self.try_append_data(data.as_ref());
+        self
+    }
+
+    /// Finishes the payload as is.
+    ///
+    /// This is used when the device was told the length of the payload in the command.
+    ///
+    /// When Ok, it returns a slice with the built payload.
+    /// If the buffer was not long enough,
+    /// then an Err is returned with the size that was required for it to succeed.
+    pub fn finish(self) -> Result<&'a [u8], usize> {
+        self.built()
+    }
+
+    /// Finishes the payload with the [CTRL_Z] byte that tells the device the payload is complete.
+    ///
+    /// When Ok, it returns a slice with the built payload.
+    /// It's an error when the buffer was not long enough or the payload contains a [CTRL_Z] or [ESC] byte.
+    pub fn finish_with_ctrl_z(mut self) -> Result<&'a [u8], PayloadError> {
+        let data_length = self.index;
+        self.try_append_data(&[CTRL_Z]);
+        let payload = self.finish().map_err(PayloadError::BufferTooSmall)?;
+
+        match payload[..data_length]
+            .iter()
+            .position(|byte| *byte == CTRL_Z || *byte == ESC)
+        {
+            Some(index) => Err(PayloadError::ReservedByte { index }),
+            None => Ok(payload),
+        }
+    }
+
+    /// Finishes the payload with the [ESC] byte that tells the device to discard the payload.
+    ///
+    /// See [Self::finish] for the return value.
+    pub fn finish_with_abort(mut self) -> Result<&'a [u8], usize> {
+        self.try_append_data(&[ESC]);
+        self.finish()
+    }
+}
+
 impl<'a, F: Finishable> CommandBuilder<'a, F> {
     /// Finishes the builder.
     ///
@@ -261,15 +851,25 @@ impl<'a, F: Finishable> CommandBuilder<'a, F> {
             }
         }
         self.try_append_data(terminator);
-
-        if self.index > self.buffer.len() {
-            Err(self.index)
-        } else {
-            Ok(&self.buffer[0..self.index])
-        }
+        self.built()
     }
 }
 
+/// The case of the letters of hex parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HexCase {
+    /// `1A2B`
+    Upper,
+    /// `1a2b`
+    Lower,
+}
+
+/// The byte (Ctrl-Z) that ends a payload
+pub const CTRL_Z: u8 = 0x1A;
+/// The byte (ESC) that aborts a payload
+pub const ESC: u8 = 0x1B;
+
 /// Marker struct for uninitialized builders.
 pub struct Uninitialized;
 /// Marker struct for initialized builders.
@@ -277,23 +877,62 @@ pub struct Uninitialized;
 pub struct Initialized<T>(core::marker::PhantomData<T>);
 
 /// Marker struct for builders that produce a test command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Test;
 /// Marker struct for builders that produce a query command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Query;
 /// Marker struct for builders that produce a set command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Set;
 /// Marker struct for builders that produce a execute command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Execute;
 
+/// Marker struct for builders that produce a basic command, like `E0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Basic;
+/// Marker struct for builders that produce an ampersand command, like `&F`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ampersand;
+/// Marker struct for builders that produce an S-register command, like `S0=2`.
+pub struct SRegister;
+/// Marker struct for builders of which the basic command is complete.
+pub struct Complete;
+/// Marker struct for builders that produce a dial command, like `D*99#`.
+pub struct Dial;
+/// Marker struct for builders that produce the payload after a `>` prompt.
+pub struct Payload;
+
 /// A trait that can be implemented for marker structs to indicate that the command is ready to be finished.
-pub trait Finishable {}
+pub trait Finishable {
+    /// The data that must be put after the command when another command follows on the same line.
+    const CHAIN_SEPARATOR: &'static [u8] = b";";
+}
 impl Finishable for Test {}
 impl Finishable for Query {}
 impl Finishable for Set {}
 impl Finishable for Execute {}
+impl Finishable for Basic {
+    const CHAIN_SEPARATOR: &'static [u8] = b"";
+}
+impl Finishable for Ampersand {
+    const CHAIN_SEPARATOR: &'static [u8] = b"";
+}
+impl Finishable for Complete {
+    const CHAIN_SEPARATOR: &'static [u8] = b"";
+}
 
 /// A trait that can be implemented for marker structs to indicate that the command is ready to be named.
 pub trait Nameable {
+    /// The data that must be put before a name to comply with the type of command that is named.
+    const NAME_PREFIX: &'static [u8] = b"";
     /// The data that must be put after a name to comply with the type of command that is named.
     const NAME_SUFFIX: &'static [u8];
 }
@@ -309,6 +948,18 @@ impl Nameable for Set {
 impl Nameable for Execute {
     const NAME_SUFFIX: &'static [u8] = b"";
 }
+impl Nameable for Basic {
+    const NAME_SUFFIX: &'static [u8] = b"";
+}
+impl Nameable for Ampersand {
+    const NAME_PREFIX: &'static [u8] = b"&";
+    const NAME_SUFFIX: &'static [u8] = b"";
+}
+
+/// A trait that is implemented for the marker structs of the commands that can have a numeric value.
+pub trait BasicValue {}
+impl BasicValue for Basic {}
+impl BasicValue for Ampersand {}
 
 #[cfg(test)]
 mod tests {
@@ -365,6 +1016,326 @@ mod tests {
         assert_eq!(core::str::from_utf8(value).unwrap(), "AT+EXECUTE\r\n");
     }
 
+    #[test]
+    fn test_wide_int_parameters() {
+        let mut buffer = [0; 128];
+        let value = CommandBuilder::create_set(&mut buffer, true)
+            .named("+WIDE")
+            .with_int_parameter_as(u32::MAX)
+            .with_int_parameter_as(i64::MIN)
+            .with_optional_int_parameter_as(Some(u64::MAX))
+            .with_int_parameter_as(-1i8)
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "AT+WIDE=4294967295,-9223372036854775808,18446744073709551615,-1\r\n"
+        );
+    }
+
+    #[test]
+    fn test_into_int_parameters() {
+        enum Mode {
+            Auto = 2,
+        }
+
+        impl From<Mode> for i32 {
+            fn from(mode: Mode) -> Self {
+                mode as i32
+            }
+        }
+
+        let mut buffer = [0; 128];
+        let value = CommandBuilder::create_set(&mut buffer, true)
+            .named("+MODE")
+            .with_int_parameter(true)
+            .with_int_parameter(Mode::Auto)
+            .with_optional_int_parameter(Some(false))
+            .finish()
+            .unwrap();
+
+        assert_eq!(core::str::from_utf8(value).unwrap(), "AT+MODE=1,2,0\r\n");
+    }
+
+    #[test]
+    fn test_hex_parameters() {
+        let mut buffer = [0; 128];
+        let value = CommandBuilder::create_set(&mut buffer, true)
+            .named("+QCFG")
+            .with_string_parameter("band")
+            .with_hex_parameter(0, 0, HexCase::Upper)
+            .with_hex_parameter(0x80084u32, 0, HexCase::Upper)
+            .with_quoted_hex_parameter(0x1A2Bu16, 8, HexCase::Lower)
+            .with_hex_parameter(-1i16, 2, HexCase::Upper)
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "AT+QCFG=\"band\",0,80084,\"00001a2b\",FFFF\r\n"
+        );
+    }
+
+    #[test]
+    fn test_hex_bytes_parameters() {
+        let mut buffer = [0; 128];
+        let value = CommandBuilder::create_set(&mut buffer, true)
+            .named("+NSOST")
+            .with_int_parameter(0)
+            .with_raw_parameter("1.2.3.4")
+            .with_int_parameter(5683)
+            .with_int_parameter(4)
+            .with_hex_bytes_parameter([0xDE, 0xAD, 0x0B, 0xEF])
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "AT+NSOST=0,1.2.3.4,5683,4,DEAD0BEF\r\n"
+        );
+
+        let value = CommandBuilder::create_set(&mut buffer, true)
+            .named("+CSIM")
+            .with_int_parameter(10)
+            .with_quoted_hex_bytes_parameter(b"\xA0\xA4\x00\x00\x02")
+            .with_quoted_hex_bytes_parameter(b"")
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "AT+CSIM=10,\"A0A4000002\",\"\"\r\n"
+        );
+
+        let mut buffer = [0; 12];
+        assert!(CommandBuilder::create_set(&mut buffer, true)
+            .named("+X")
+            .with_hex_bytes_parameter([0; 4])
+            .finish()
+            .is_err());
+    }
+
+    #[test]
+    fn test_chained_commands() {
+        let mut buffer = [0; 128];
+        let value = CommandBuilder::create_test(&mut buffer, true)
+            .named("+CGDCONT")
+            .chain_set()
+            .named("+CGDCONT")
+            .with_int_parameter(1)
+            .with_string_parameter("IP")
+            .with_empty_parameter()
+            .chain_execute()
+            .named("+CGMR")
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "AT+CGDCONT=?;+CGDCONT=1,\"IP\",;+CGMR\r\n"
+        );
+
+        let value = CommandBuilder::create_query(&mut buffer, false)
+            .named("+CREG")
+            .chain_query()
+            .named("+CEREG")
+            .finish_with(b"\r")
+            .unwrap();
+
+        assert_eq!(core::str::from_utf8(value).unwrap(), "+CREG?;+CEREG?\r");
+    }
+
+    #[test]
+    fn test_basic_commands() {
+        let mut buffer = [0; 128];
+
+        let value = CommandBuilder::create_basic(&mut buffer, true)
+            .named("Z")
+            .finish()
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "ATZ\r\n");
+
+        let value = CommandBuilder::create_ampersand(&mut buffer, true)
+            .named("F")
+            .chain_ampersand()
+            .named("D")
+            .with_value(2)
+            .chain_basic()
+            .named("Q")
+            .with_value(0)
+            .chain_set()
+            .named("+CMEE")
+            .with_int_parameter(2)
+            .chain_basic()
+            .named("H")
+            .finish()
+            .unwrap();
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "AT&F&D2Q0+CMEE=2;H\r\n"
+        );
+
+        let value = CommandBuilder::create_s_register(&mut buffer, true)
+            .query(3)
+            .finish()
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "ATS3?\r\n");
+
+        let value = CommandBuilder::create_s_register(&mut buffer, false)
+            .set(7, 255)
+            .chain_basic()
+            .named("O")
+            .finish_with(b"\r")
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "S7=255O\r");
+    }
+
+    #[test]
+    fn test_dial() {
+        let mut buffer = [0; 128];
+
+        let value = CommandBuilder::create_dial(&mut buffer, true)
+            .dial_string("+31612345678")
+            .unwrap()
+            .voice()
+            .chain_set()
+            .named("+VTS")
+            .with_int_parameter(1)
+            .finish()
+            .unwrap();
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "ATD+31612345678;+VTS=1\r\n"
+        );
+
+        // All the modifiers of V.250 and 27.007
+        let value = CommandBuilder::create_dial(&mut buffer, true)
+            .dial_string("T9W0,P!@1234*#ABCDabcdIiGg,")
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "ATDT9W0,P!@1234*#ABCDabcdIiGg,\r\n"
+        );
+
+        let value = CommandBuilder::create_dial(&mut buffer, false)
+            .phonebook_entry(None, 12)
+            .unwrap()
+            .finish_with(b"\r")
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "D>12\r");
+
+        let value = CommandBuilder::create_dial(&mut buffer, true)
+            .phonebook_entry(Some("ME"), 3)
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "ATD>ME3\r\n");
+    }
+
+    #[test]
+    fn test_dial_invalid() {
+        let mut buffer = [0; 128];
+
+        for (dial_string, index) in [
+            ("123;", 3),
+            (">SM1", 0),
+            ("12 34", 2),
+            ("0612-345", 4),
+            ("\"123\"", 0),
+            ("E", 0),
+        ] {
+            assert_eq!(
+                CommandBuilder::create_dial(&mut buffer, true)
+                    .dial_string(dial_string)
+                    .err(),
+                Some(InvalidDialString { index }),
+                "{dial_string}"
+            );
+        }
+
+        assert_eq!(
+            CommandBuilder::create_dial(&mut buffer, true)
+                .phonebook_entry(Some("S;"), 1)
+                .err(),
+            Some(InvalidDialString { index: 1 })
+        );
+
+        let mut buffer = [0; 8];
+        assert_eq!(
+            CommandBuilder::create_dial(&mut buffer, true)
+                .dial_string("*99#")
+                .unwrap()
+                .finish(),
+            Err(9)
+        );
+    }
+
+    #[test]
+    fn test_payload() {
+        let mut buffer = [0; 16];
+
+        let value = CommandBuilder::create_payload(&mut buffer)
+            .with_data("Hello")
+            .with_data([b' ', 0, b'\r', b'\n'])
+            .finish()
+            .unwrap();
+        assert_eq!(value, b"Hello \0\r\n");
+
+        let value = CommandBuilder::create_payload(&mut buffer)
+            .with_data("Hi")
+            .finish_with_ctrl_z()
+            .unwrap();
+        assert_eq!(value, [b'H', b'i', CTRL_Z]);
+
+        let value = CommandBuilder::create_payload(&mut buffer)
+            .with_data("Hi")
+            .finish_with_abort()
+            .unwrap();
+        assert_eq!(value, [b'H', b'i', ESC]);
+
+        assert_eq!(
+            CommandBuilder::create_payload(&mut buffer[..4])
+                .with_data("Hello")
+                .finish_with_ctrl_z(),
+            Err(PayloadError::BufferTooSmall(6))
+        );
+
+        for reserved in [CTRL_Z, ESC] {
+            assert_eq!(
+                CommandBuilder::create_payload(&mut buffer)
+                    .with_data("Hi")
+                    .with_data([reserved, b'!'])
+                    .finish_with_ctrl_z(),
+                Err(PayloadError::ReservedByte { index: 2 })
+            );
+
+            let value = CommandBuilder::create_payload(&mut buffer)
+                .with_data([reserved])
+                .finish()
+                .unwrap();
+            assert_eq!(value, [reserved]);
+        }
+    }
+
+    #[test]
+    fn test_escaped_string_parameter() {
+        let mut buffer = [0; 128];
+        let value = CommandBuilder::create_set(&mut buffer, true)
+            .named("+HTTPCLIENT")
+            .with_escaped_string_parameter("http://host/?a=1,b=\"2\"", Escaping::Backslash)
+            .with_escaped_string_parameter("C:\\data\r\n", Escaping::Hex)
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "AT+HTTPCLIENT=\"http://host/?a=1\\,b=\\\"2\\\"\",\"C:\\5Cdata\\0D\\0A\"\r\n"
+        );
+    }
+
     #[test]
     fn test_buffer_too_short() {
         let mut buffer = [0; 5];
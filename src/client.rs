@@ -0,0 +1,562 @@
+//! Module with clients that send commands over an `embedded-io` or `embedded-io-async` interface and collect the responses
+
+use crate::digester::DigestItem;
+#[cfg(feature = "embedded-io")]
+use crate::digester::Digester;
+use crate::result_code::FinalResult;
+#[cfg(feature = "embedded-io")]
+use crate::urc::UrcDispatcher;
+#[cfg(feature = "embedded-io")]
+use embedded_io::{Read, ReadReady, Write};
+
+#[cfg(feature = "embedded-io-async")]
+pub mod asynch;
+
+/// A monotonic clock that the client uses for its timeouts
+#[cfg(feature = "embedded-io")]
+pub trait Clock {
+    /// The current time in milliseconds since some point in the past
+    fn now_ms(&self) -> u64;
+}
+
+/// # AtClient
+/// Sends commands to a device and collects its responses
+///
+/// The client writes a command, like the one a [CommandBuilder](crate::builder::CommandBuilder) built,
+/// and reads until the device answers with a final result code or asks for data with the `>` prompt.
+/// The echo of the command is skipped and the rest is given as it was sent, see [Response].
+///
+/// What the device sent since the last response is not part of the next one.
+/// Before a command is written, the URCs that came in meanwhile go to their handlers
+/// and the rest is dropped.
+/// When a command timed out, the client waits for its late final result code first, up to the timeout,
+/// so it doesn't end the response of the next command.
+///
+/// The bytes are only read when the interface is [ReadReady],
+/// so the client can give up when no final result code came in before the timeout.
+///
+/// The client has two buffers of `N` bytes: one to split the incoming bytes into lines
+/// and one to collect the response in. So nothing is allocated.
+///
+/// ## Example
+/// ```rust
+/// # use std::cell::Cell;
+/// # use std::convert::Infallible;
+/// # struct Uart(&'static [u8], bool);
+/// # impl embedded_io::ErrorType for Uart { type Error = Infallible; }
+/// # impl embedded_io::Read for Uart {
+/// #     fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Infallible> {
+/// #         let length = buffer.len().min(self.0.len());
+/// #         buffer[..length].copy_from_slice(&self.0[..length]);
+/// #         self.0 = &self.0[length..];
+/// #         Ok(length)
+/// #     }
+/// # }
+/// # impl embedded_io::ReadReady for Uart {
+/// #     fn read_ready(&mut self) -> Result<bool, Infallible> { Ok(self.1 && !self.0.is_empty()) }
+/// # }
+/// # impl embedded_io::Write for Uart {
+/// #     fn write(&mut self, buffer: &[u8]) -> Result<usize, Infallible> {
+/// #         // The device answers once the command is written
+/// #         self.1 = true;
+/// #         Ok(buffer.len())
+/// #     }
+/// #     fn flush(&mut self) -> Result<(), Infallible> { Ok(()) }
+/// # }
+/// # struct SysTick(Cell<u64>);
+/// # impl at_commands::client::Clock for SysTick {
+/// #     fn now_ms(&self) -> u64 { self.0.replace(self.0.get() + 1) }
+/// # }
+/// # let uart = Uart(b"AT+CSQ\r\r\n+CSQ: 20,99\r\n\r\nOK\r\n", false);
+/// # let clock = SysTick(Cell::new(0));
+/// use at_commands::builder::CommandBuilder;
+/// use at_commands::client::AtClient;
+/// use at_commands::parser::CommandParser;
+/// use at_commands::result_code::FinalResult;
+///
+/// let mut client = AtClient::<_, _, 128>::new(uart, clock, 1000);
+///
+/// let mut buffer = [0; 32];
+/// let command = CommandBuilder::create_execute(&mut buffer, true)
+///     .named("+CSQ")
+///     .finish()
+///     .unwrap();
+///
+/// let response = client.send(command).unwrap();
+/// assert_eq!(response.result(), Some(FinalResult::Ok));
+///
+/// let (rssi, ber) = CommandParser::parse(response.raw())
+///     .expect_identifier(b"\r\n+CSQ:")
+///     .expect_int_parameter()
+///     .expect_int_parameter()
+///     .expect_identifier(b"\r\n\r\nOK\r\n")
+///     .finish()
+///     .unwrap();
+/// assert_eq!((rssi, ber), (20, 99));
+/// ```
+#[cfg(feature = "embedded-io")]
+pub struct AtClient<T, C, const N: usize> {
+    io: T,
+    clock: C,
+    timeout_ms: u32,
+    digester: Digester<N>,
+    /// The bytes that were read but didn't fit in the digester yet
+    chunk: [u8; 32],
+    chunk_range: core::ops::Range<usize>,
+    /// The last command didn't get to its final result code, which may still come in
+    late_result: bool,
+    response: [u8; N],
+}
+
+/// The response of the device to a command
+///
+/// The raw bytes of the response are what the device sent after the echo of the command, byte for byte.
+/// So the line terminators and empty lines are kept and binary data,
+/// like the data of `+QIRD` that is read with [CommandParser::expect_length_prefixed_data](crate::parser::CommandParser::expect_length_prefixed_data),
+/// comes through as it was sent.
+/// Only the URCs that went to a handler are left out.
+///
+/// The end of the response is found line by line,
+/// so binary data with a line that is a final result code still ends the response there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Response<'a> {
+    /// The device ended the response with a final result code
+    Complete {
+        /// The raw bytes of the response, up to and including the final result code and its line terminator
+        raw: &'a [u8],
+        /// The final result code the response ended with
+        result: FinalResult<'a>,
+    },
+    /// The device asks for the data of the command with the `>` prompt, like it does for `AT+CMGS`.
+    ///
+    /// The data is written with the next send, which then gets the final result code of the command.
+    Prompt {
+        /// The raw bytes of the response, up to and including the prompt
+        raw: &'a [u8],
+    },
+}
+
+impl<'a> Response<'a> {
+    /// The raw bytes of the response
+    pub fn raw(&self) -> &'a [u8] {
+        match self {
+            Response::Complete { raw, .. } | Response::Prompt { raw } => raw,
+        }
+    }
+
+    /// The final result code the response ended with, or None if it ended with the prompt
+    pub fn result(&self) -> Option<FinalResult<'a>> {
+        match self {
+            Response::Complete { result, .. } => Some(*result),
+            Response::Prompt { .. } => None,
+        }
+    }
+}
+
+/// The ways sending a command can fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClientError<E> {
+    /// The interface gave an error
+    Io(E),
+    /// No final result code came in before the timeout.
+    ///
+    /// The async client doesn't time out by itself.
+    Timeout,
+    /// A line or the whole response didn't fit in the buffers of the client
+    Overflow,
+}
+
+#[cfg(feature = "embedded-io")]
+impl<T, C, const N: usize> AtClient<T, C, N>
+where
+    T: Read + ReadReady + Write,
+    C: Clock,
+{
+    /// Creates a client that waits `timeout_ms` milliseconds for the response to a command
+    pub fn new(io: T, clock: C, timeout_ms: u32) -> Self {
+        Self {
+            io,
+            clock,
+            timeout_ms,
+            digester: Digester::new(),
+            chunk: [0; 32],
+            chunk_range: 0..0,
+            late_result: false,
+            response: [0; N],
+        }
+    }
+
+    /// Sets how long the client waits for the response to a command
+    pub fn set_timeout(&mut self, timeout_ms: u32) {
+        self.timeout_ms = timeout_ms;
+    }
+
+    /// Sends the command and collects the response.
+    ///
+    /// The command must already have its line terminator.
+    /// Everything that comes in before the final result code is part of the response.
+    /// Use [Self::send_with_urcs] to keep the unsolicited result codes out of it.
+    ///
+    /// After a [Response::Prompt], the data for the command is sent with this as well.
+    pub fn send(&mut self, command: &[u8]) -> Result<Response<'_>, ClientError<T::Error>> {
+        self.exchange(command, |item, _| Some(item))
+    }
+
+    /// Sends the command and collects the response,
+    /// giving the lines that come in meanwhile to the URC handlers they belong to.
+    ///
+    /// The lines that start with the name of the command, like `+CREG:` for `AT+CREG?`,
+    /// are part of the response even if there's a handler for them.
+    pub fn send_with_urcs<const U: usize>(
+        &mut self,
+        command: &[u8],
+        urcs: &mut UrcDispatcher<'_, U>,
+    ) -> Result<Response<'_>, ClientError<T::Error>> {
+        self.exchange(command, |item, response_prefix| {
+            urcs.dispatch_item_for(item, response_prefix)
+        })
+    }
+
+    /// Gives back the interface and the clock
+    pub fn release(self) -> (T, C) {
+        (self.io, self.clock)
+    }
+
+    fn exchange<F>(
+        &mut self,
+        command: &[u8],
+        mut dispatch: F,
+    ) -> Result<Response<'_>, ClientError<T::Error>>
+    where
+        F: for<'i> FnMut(DigestItem<'i>, Option<&[u8]>) -> Option<DigestItem<'i>>,
+    {
+        self.discard(&mut dispatch)?;
+
+        self.io.write_all(command).map_err(ClientError::Io)?;
+        self.io.flush().map_err(ClientError::Io)?;
+
+        let mut prefix_buffer = [0; 32];
+        let response_prefix = response_prefix(command, &mut prefix_buffer);
+
+        let start = self.clock.now_ms();
+        let mut response_length = 0;
+        // Until the final result code is in, a late one may still come
+        self.late_result = true;
+
+        loop {
+            while let Some((item, bytes)) = self.digester.next_item_with_bytes() {
+                let Some(item) = dispatch(item, response_prefix) else {
+                    // A URC
+                    continue;
+                };
+
+                match collect(&mut self.response, &mut response_length, item, bytes) {
+                    Collected::More => {}
+                    Collected::Overflow => return Err(ClientError::Overflow),
+                    end => {
+                        // The final result code of a command with a prompt comes after its data
+                        self.late_result = false;
+                        return to_response(&self.response[..response_length], end)
+                            .ok_or(ClientError::Overflow);
+                    }
+                }
+            }
+
+            if !self.fill(start)? {
+                return Err(ClientError::Timeout);
+            }
+        }
+    }
+
+    /// Handles what came in since the last response, so it doesn't end up in the next one.
+    ///
+    /// The URCs go to their handlers and the rest is dropped, including a line that isn't complete yet.
+    /// If the last command didn't get its final result code, that is waited for first, up to the timeout.
+    fn discard<F>(&mut self, dispatch: &mut F) -> Result<(), ClientError<T::Error>>
+    where
+        F: for<'i> FnMut(DigestItem<'i>, Option<&[u8]>) -> Option<DigestItem<'i>>,
+    {
+        let start = self.clock.now_ms();
+
+        loop {
+            while let Some(item) = self.digester.next_item() {
+                if let Some(DigestItem::FinalResult(_)) = dispatch(item, None) {
+                    self.late_result = false;
+                }
+            }
+
+            if self.late_result {
+                // Gave up on it
+                if !self.fill(start)? {
+                    self.late_result = false;
+                }
+            } else if !self.chunk_range.is_empty() {
+                self.chunk_range.start += self.digester.feed(&self.chunk[self.chunk_range.clone()]);
+            } else if self.io.read_ready().map_err(ClientError::Io)? {
+                self.chunk_range = 0..self.io.read(&mut self.chunk).map_err(ClientError::Io)?;
+                // The end of the stream
+                if self.chunk_range.is_empty() {
+                    break;
+                }
+            } else {
+                break;
+            }
+        }
+
+        self.digester.clear();
+        Ok(())
+    }
+
+    /// Gives the bytes that were read before to the digester, or reads new ones if it took them all.
+    ///
+    /// Returns false when the timeout since `start` has passed.
+    fn fill(&mut self, start: u64) -> Result<bool, ClientError<T::Error>> {
+        if !self.chunk_range.is_empty() {
+            self.chunk_range.start += self.digester.feed(&self.chunk[self.chunk_range.clone()]);
+            return Ok(true);
+        }
+
+        if self.clock.now_ms().wrapping_sub(start) >= u64::from(self.timeout_ms) {
+            return Ok(false);
+        }
+
+        if self.io.read_ready().map_err(ClientError::Io)? {
+            self.chunk_range = 0..self.io.read(&mut self.chunk).map_err(ClientError::Io)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Finds the prefix of the lines of the response to an extended command, like `+CSQ:` for `AT+CSQ`.
+///
+/// The prefix is put in the buffer. Returns None for a basic command or a name that doesn't fit.
+fn response_prefix<'b>(command: &[u8], buffer: &'b mut [u8]) -> Option<&'b [u8]> {
+    let name = crate::dce::IncomingCommand::parse(command)
+        .ok()?
+        .name()
+        .as_bytes();
+
+    // Extended commands start with a symbol, like `+`, `^` or `$`
+    match name.first() {
+        Some(first) if !first.is_ascii_alphabetic() && *first != b'&' => {}
+        _ => return None,
+    }
+
+    let prefix = buffer.get_mut(..name.len() + 1)?;
+    prefix[..name.len()].copy_from_slice(name);
+    prefix[name.len()] = b':';
+    Some(prefix)
+}
+
+/// What the response is after an item was collected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Collected {
+    /// The response isn't complete yet
+    More,
+    /// The response ended with a final result code
+    Complete,
+    /// The response ended with the prompt
+    Prompt,
+    /// The response didn't fit
+    Overflow,
+}
+
+/// Adds the bytes of the item to the response in the buffer of which `length` bytes are collected so far.
+///
+/// The echo and the prompt only count as such before the first line of the response,
+/// after that they're part of the data in it.
+fn collect(buffer: &mut [u8], length: &mut usize, item: DigestItem, bytes: &[u8]) -> Collected {
+    let collected = match item {
+        DigestItem::Echo(_) if *length == 0 => return Collected::More,
+        DigestItem::Prompt if *length == 0 => Collected::Prompt,
+        DigestItem::FinalResult(_) => Collected::Complete,
+        DigestItem::Overflow(_) => return Collected::Overflow,
+        DigestItem::Echo(_) | DigestItem::Prompt | DigestItem::Line(_) => Collected::More,
+    };
+
+    match buffer.get_mut(*length..*length + bytes.len()) {
+        Some(target) => target.copy_from_slice(bytes),
+        None => return Collected::Overflow,
+    }
+    *length += bytes.len();
+
+    collected
+}
+
+/// Reads the final result code back from a response that was collected with [collect]
+fn to_response(raw: &[u8], collected: Collected) -> Option<Response<'_>> {
+    match collected {
+        Collected::Prompt => Some(Response::Prompt { raw }),
+        _ => FinalResult::split_response(raw).map(|(_, result)| Response::Complete { raw, result }),
+    }
+}
+
+#[cfg(all(test, feature = "embedded-io"))]
+mod tests {
+    use super::*;
+    use crate::result_code::CmeError;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    /// A device that gives its next answer every time something is written, a few bytes at a time
+    struct FakeDevice {
+        answers: &'static [&'static [u8]],
+        answer: &'static [u8],
+        written: Vec<u8>,
+    }
+
+    impl embedded_io::ErrorType for FakeDevice {
+        type Error = Infallible;
+    }
+
+    impl Read for FakeDevice {
+        fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Infallible> {
+            let length = buffer.len().min(self.answer.len()).min(5);
+            buffer[..length].copy_from_slice(&self.answer[..length]);
+            self.answer = &self.answer[length..];
+            Ok(length)
+        }
+    }
+
+    impl ReadReady for FakeDevice {
+        fn read_ready(&mut self) -> Result<bool, Infallible> {
+            Ok(!self.answer.is_empty())
+        }
+    }
+
+    impl Write for FakeDevice {
+        fn write(&mut self, buffer: &[u8]) -> Result<usize, Infallible> {
+            self.written.extend_from_slice(buffer);
+            if let [answer, answers @ ..] = self.answers {
+                self.answer = answer;
+                self.answers = answers;
+            }
+            Ok(buffer.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    /// A clock that goes forward a millisecond every time it's read
+    struct FakeClock(Cell<u64>);
+
+    impl Clock for FakeClock {
+        fn now_ms(&self) -> u64 {
+            self.0.replace(self.0.get() + 1)
+        }
+    }
+
+    fn fake_client<const N: usize>(
+        answers: &'static [&'static [u8]],
+    ) -> AtClient<FakeDevice, FakeClock, N> {
+        AtClient::new(
+            FakeDevice {
+                answers,
+                answer: b"",
+                written: Vec::new(),
+            },
+            FakeClock(Cell::new(0)),
+            100,
+        )
+    }
+
+    #[test]
+    fn test_send() {
+        let mut client = fake_client::<64>(&[b"AT+CGMR\r\r\n+CGMR: 1.0\r\nbuild 2\r\n\r\nOK\r\n"]);
+
+        let response = client.send(b"AT+CGMR\r\n").unwrap();
+        assert_eq!(
+            response,
+            Response::Complete {
+                raw: b"\r\n+CGMR: 1.0\r\nbuild 2\r\n\r\nOK\r\n",
+                result: FinalResult::Ok,
+            }
+        );
+
+        let (device, _) = client.release();
+        assert_eq!(device.written, b"AT+CGMR\r\n");
+
+        let mut client = fake_client::<64>(&[b"\r\n+CME ERROR: SIM not inserted\r\n"]);
+        let response = client.send(b"AT+CPIN?\r\n").unwrap();
+        assert_eq!(response.raw(), b"\r\n+CME ERROR: SIM not inserted\r\n");
+        assert_eq!(
+            response.result(),
+            Some(FinalResult::CmeError(CmeError::Text("SIM not inserted")))
+        );
+    }
+
+    #[test]
+    fn test_send_binary_data() {
+        use crate::parser::CommandParser;
+
+        // The data has line terminators, an empty line and a `>` in it
+        let mut client = fake_client::<64>(&[b"\r\n+QIRD: 6\r\n\r\n\r\n>\x00\r\n\r\nOK\r\n"]);
+
+        let response = client.send(b"AT+QIRD=0,6\r\n").unwrap();
+        assert_eq!(response.result(), Some(FinalResult::Ok));
+
+        let (_, data) = CommandParser::parse(response.raw())
+            .expect_identifier(b"\r\n+QIRD:")
+            .expect_int_parameter()
+            .expect_length_prefixed_data(b"\r\n")
+            .expect_identifier(b"\r\n\r\nOK\r\n")
+            .finish()
+            .unwrap();
+        assert_eq!(data, b"\r\n\r\n>\x00");
+    }
+
+    #[test]
+    fn test_send_prompt() {
+        let mut client =
+            fake_client::<64>(&[b"AT+CMGS=\"123\"\r\r\n> ", b"\r\n+CMGS: 5\r\n\r\nOK\r\n"]);
+
+        let response = client.send(b"AT+CMGS=\"123\"\r").unwrap();
+        assert_eq!(response, Response::Prompt { raw: b"\r\n> " });
+        assert_eq!(response.result(), None);
+
+        // The data gets the final result code of the command
+        let response = client.send(b"hello\x1A").unwrap();
+        assert_eq!(response.raw(), b"\r\n+CMGS: 5\r\n\r\nOK\r\n");
+        assert_eq!(response.result(), Some(FinalResult::Ok));
+    }
+
+    #[test]
+    fn test_send_with_urcs() {
+        let mut client =
+            fake_client::<64>(&[b"\r\nRING\r\n\r\n+CSQ: 20,99\r\n\r\n+CREG: 1\r\n\r\nOK\r\n"]);
+
+        let mut rings = 0;
+        let mut on_ring = |_: &[u8]| rings += 1;
+        let mut registrations = 0;
+        let mut on_creg = |_: &[u8]| registrations += 1;
+
+        {
+            let mut urcs = UrcDispatcher::<2>::new();
+            urcs.register(b"RING", &mut on_ring).unwrap();
+            urcs.register(b"+CREG:", &mut on_creg).unwrap();
+
+            let response = client.send_with_urcs(b"AT+CSQ\r\n", &mut urcs).unwrap();
+            assert_eq!(response.raw(), b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n");
+        }
+
+        assert_eq!((rings, registrations), (1, 1));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut client = fake_client::<64>(&[b"\r\n+CSQ: 20,99\r\n"]);
+        assert_eq!(client.send(b"AT+CSQ\r\n"), Err(ClientError::Timeout));
+
+        // The line doesn't fit in the digester
+        let mut client = fake_client::<8>(&[b"\r\n+CGMR: 1.0.0\r\n\r\nOK\r\n"]);
+        assert_eq!(client.send(b"AT+CGMR\r\n"), Err(ClientError::Overflow));
+
+        // The lines fit, but the whole response doesn't
+        let mut client = fake_client::<16>(&[b"\r\n+CPBR: 1\r\n+CPBR: 2\r\n\r\nOK\r\n"]);
+        assert_eq!(client.send(b"AT+CPBR=1,2\r\n"), Err(ClientError::Overflow));
+    }
+}
@@ -0,0 +1,596 @@
+//! The async client that sends commands over an [embedded_io_async] interface.
+//!
+//! The client is split in two: an [AtReader] that reads everything the device sends
+//! and an [AtHandle] that sends the commands and waits for their responses.
+//! They meet in an [AtChannel], which can be a `static` to give the reader a task of its own.
+
+use super::{collect, response_prefix, to_response, ClientError, Collected, Response};
+use crate::digester::{DigestItem, Digester};
+use crate::urc::UrcDispatcher;
+use core::cell::RefCell;
+use embassy_futures::select::{select, Either};
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+
+/// # AtChannel
+/// The state that the [AtReader] and the [AtHandle] share
+///
+/// It holds the response of the pending command in a buffer of `N` bytes.
+/// The mutex `M` protects it, which only needs to be a critical section
+/// if the reader and the handle run in different executors or interrupts.
+///
+/// ## Example
+/// ```rust
+/// use at_commands::builder::CommandBuilder;
+/// use at_commands::client::asynch::{AtChannel, AtHandle, AtReader};
+/// use at_commands::parser::CommandParser;
+/// use at_commands::urc::UrcDispatcher;
+/// use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+/// use embedded_hal_async::delay::DelayNs;
+///
+/// type Mutex = CriticalSectionRawMutex;
+///
+/// static CHANNEL: AtChannel<Mutex, 256> = AtChannel::new();
+///
+/// // This runs in a task of its own
+/// async fn reader_task<R: embedded_io_async::Read>(mut reader: AtReader<'static, R, Mutex, 256>) {
+///     let mut on_ring = |_: &[u8]| {
+///         // Answer the call
+///     };
+///
+///     let mut urcs = UrcDispatcher::<1>::new();
+///     urcs.register(b"RING", &mut on_ring).unwrap();
+///
+///     reader.run(&mut urcs).await.unwrap();
+/// }
+///
+/// async fn signal_quality<W: embedded_io_async::Write, D: DelayNs>(
+///     handle: &mut AtHandle<'static, W, D, Mutex, 256>,
+/// ) -> Option<(i32, i32)> {
+///     let mut buffer = [0; 16];
+///     let command = CommandBuilder::create_execute(&mut buffer, true)
+///         .named("+CSQ")
+///         .finish()
+///         .ok()?;
+///
+///     let response = handle.send(command).await.ok()?;
+///
+///     CommandParser::parse(response.raw())
+///         .expect_identifier(b"\r\n+CSQ:")
+///         .expect_int_parameter()
+///         .expect_int_parameter()
+///         .finish()
+///         .ok()
+/// }
+///
+/// fn start<R: embedded_io_async::Read, W: embedded_io_async::Write, D: DelayNs>(
+///     uart_rx: R,
+///     uart_tx: W,
+///     delay: D,
+/// ) {
+///     // Wait up to a second for every response
+///     let (reader, handle) = CHANNEL.split(uart_rx, uart_tx, delay, 1000);
+///     // Spawn the reader task and give the handle to the driver
+/// }
+/// ```
+pub struct AtChannel<M: RawMutex, const N: usize> {
+    state: Mutex<M, RefCell<State<N>>>,
+    completed: Signal<M, Completion>,
+}
+
+struct State<const N: usize> {
+    /// A command is waiting for its response
+    pending: bool,
+    /// The prefix of the lines of the response, like `+CSQ:` for `AT+CSQ`, in the first bytes
+    response_prefix: [u8; 32],
+    response_prefix_length: Option<usize>,
+    /// The length of the response that is collected so far
+    length: usize,
+    buffer: [u8; N],
+}
+
+/// How the response of the pending command ended
+enum Completion {
+    /// The response has the given length and ended like this
+    Response(usize, Collected),
+    /// The response didn't fit in the buffer
+    Overflow,
+}
+
+impl<M: RawMutex, const N: usize> AtChannel<M, N> {
+    /// Creates the channel without a pending command
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(RefCell::new(State {
+                pending: false,
+                response_prefix: [0; 32],
+                response_prefix_length: None,
+                length: 0,
+                buffer: [0; N],
+            })),
+            completed: Signal::new(),
+        }
+    }
+
+    /// Splits the client in the reader that reads from the device and the handle that writes to it.
+    ///
+    /// The handle waits `timeout_ms` milliseconds of the `delay` for the response to a command.
+    pub fn split<R: Read, W: Write, D: DelayNs>(
+        &self,
+        reader: R,
+        writer: W,
+        delay: D,
+        timeout_ms: u32,
+    ) -> (AtReader<'_, R, M, N>, AtHandle<'_, W, D, M, N>) {
+        (
+            AtReader {
+                io: reader,
+                channel: self,
+                digester: Digester::new(),
+            },
+            AtHandle {
+                io: writer,
+                channel: self,
+                delay,
+                timeout_ms,
+                in_flight: false,
+                response: [0; N],
+            },
+        )
+    }
+
+    /// Starts collecting the response of a new command
+    fn start(&self, command: &[u8]) {
+        self.state.lock(|state| {
+            let mut state = state.borrow_mut();
+            let state = &mut *state;
+            state.response_prefix_length =
+                response_prefix(command, &mut state.response_prefix).map(<[u8]>::len);
+            state.pending = true;
+            state.length = 0;
+        });
+        self.completed.reset();
+    }
+
+    /// Copies the response prefix of the pending command to the buffer
+    fn response_prefix<'b>(&self, buffer: &'b mut [u8; 32]) -> Option<&'b [u8]> {
+        self.state.lock(|state| {
+            let state = state.borrow();
+            let length = state.response_prefix_length.filter(|_| state.pending)?;
+            buffer[..length].copy_from_slice(&state.response_prefix[..length]);
+            Some(&buffer[..length])
+        })
+    }
+
+    /// Adds an item to the response of the pending command, if there is one
+    fn handle(&self, item: DigestItem<'_>, bytes: &[u8]) {
+        let completion = self.state.lock(|state| {
+            let mut state = state.borrow_mut();
+            let State {
+                pending,
+                length,
+                buffer,
+                ..
+            } = &mut *state;
+
+            // Nobody waits for it
+            if !*pending {
+                return None;
+            }
+
+            let completion = match collect(buffer, length, item, bytes) {
+                Collected::More => return None,
+                Collected::Overflow => Completion::Overflow,
+                end => Completion::Response(*length, end),
+            };
+
+            *pending = false;
+            Some(completion)
+        });
+
+        if let Some(completion) = completion {
+            self.completed.signal(completion);
+        }
+    }
+}
+
+impl<M: RawMutex, const N: usize> Default for AtChannel<M, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads everything the device sends
+///
+/// The lines are routed to the URC handlers or to the response of the pending command.
+/// Lines that are not a URC while no command is pending are dropped.
+pub struct AtReader<'c, R, M: RawMutex, const N: usize> {
+    io: R,
+    channel: &'c AtChannel<M, N>,
+    digester: Digester<N>,
+}
+
+impl<R: Read, M: RawMutex, const N: usize> AtReader<'_, R, M, N> {
+    /// Reads from the device until it reaches the end of the stream or gives an error.
+    ///
+    /// The lines that start with the prefix of one of the URC handlers go to that handler,
+    /// unless they start with the name of the pending command, like `+CREG:` for `AT+CREG?`.
+    pub async fn run<const U: usize>(
+        &mut self,
+        urcs: &mut UrcDispatcher<'_, U>,
+    ) -> Result<(), R::Error> {
+        let mut chunk = [0; 32];
+        let mut prefix_buffer = [0; 32];
+
+        loop {
+            let read = self.io.read(&mut chunk).await?;
+            if read == 0 {
+                return Ok(());
+            }
+
+            let mut fed = 0;
+            while fed < read {
+                fed += self.digester.feed(&chunk[fed..read]);
+
+                while let Some((item, bytes)) = self.digester.next_item_with_bytes() {
+                    let response_prefix = self.channel.response_prefix(&mut prefix_buffer);
+                    if let Some(item) = urcs.dispatch_item_for(item, response_prefix) {
+                        self.channel.handle(item, bytes);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sends commands to the device and waits for their responses
+///
+/// A send gives up with [ClientError::Timeout] when no final result code came in before the timeout.
+/// Its future can also be dropped to stop waiting earlier.
+///
+/// The response of a command that timed out or whose send was dropped may still come in later.
+/// So the next send first waits for its final result code, up to the timeout,
+/// and only then writes its own command.
+/// If that send is dropped as well, the late response is given up on.
+pub struct AtHandle<'c, W, D, M: RawMutex, const N: usize> {
+    io: W,
+    channel: &'c AtChannel<M, N>,
+    delay: D,
+    timeout_ms: u32,
+    /// A command was sent and its final result code didn't come in yet
+    in_flight: bool,
+    response: [u8; N],
+}
+
+impl<W: Write, D: DelayNs, M: RawMutex, const N: usize> AtHandle<'_, W, D, M, N> {
+    /// Sets the time that is waited for the response to a command
+    pub fn set_timeout(&mut self, timeout_ms: u32) {
+        self.timeout_ms = timeout_ms;
+    }
+
+    /// Sends the command and waits for the response.
+    ///
+    /// The command must already have its line terminator.
+    /// Everything that isn't a URC and comes in before the final result code is part of the response.
+    ///
+    /// After a [Response::Prompt], the data for the command is sent with this as well.
+    ///
+    /// If the last command didn't get its final result code, that is waited for first, up to the timeout.
+    pub async fn send(&mut self, command: &[u8]) -> Result<Response<'_>, ClientError<W::Error>> {
+        // The last send timed out or was dropped, so its late response would end this one.
+        // When it doesn't come in either, it's given up on.
+        if self.in_flight {
+            self.in_flight = false;
+            self.wait_for_completion().await;
+        }
+
+        // Start before writing, so a quick response isn't missed
+        self.channel.start(command);
+
+        self.in_flight = true;
+        if let Err(error) = self.write(command).await {
+            self.in_flight = false;
+            return Err(ClientError::Io(error));
+        }
+
+        // The command stays in flight after a timeout, its response may still come in
+        let Some(completion) = self.wait_for_completion().await else {
+            return Err(ClientError::Timeout);
+        };
+        self.in_flight = false;
+
+        let (length, end) = match completion {
+            Completion::Response(length, end) => (length, end),
+            Completion::Overflow => return Err(ClientError::Overflow),
+        };
+
+        self.channel.state.lock(|state| {
+            self.response[..length].copy_from_slice(&state.borrow().buffer[..length]);
+        });
+
+        to_response(&self.response[..length], end).ok_or(ClientError::Overflow)
+    }
+
+    /// Waits for the pending command to complete.
+    /// Returns `None` when the timeout passed first.
+    async fn wait_for_completion(&mut self) -> Option<Completion> {
+        match select(
+            self.channel.completed.wait(),
+            self.delay.delay_ms(self.timeout_ms),
+        )
+        .await
+        {
+            Either::First(completion) => Some(completion),
+            Either::Second(()) => None,
+        }
+    }
+
+    async fn write(&mut self, command: &[u8]) -> Result<(), W::Error> {
+        self.io.write_all(command).await?;
+        self.io.flush().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result_code::{CmeError, FinalResult};
+    use core::cell::Cell;
+    use core::convert::Infallible;
+    use core::future::{poll_fn, ready};
+    use core::task::{Poll, Waker};
+    use embassy_futures::block_on;
+    use embassy_futures::join::join3;
+    use embassy_futures::yield_now;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use std::collections::VecDeque;
+
+    /// A delay where a millisecond is a turn of the executor
+    struct Turns;
+
+    impl DelayNs for Turns {
+        async fn delay_ns(&mut self, ns: u32) {
+            for _ in 0..ns / 1_000_000 {
+                yield_now().await;
+            }
+        }
+
+        async fn delay_ms(&mut self, ms: u32) {
+            for _ in 0..ms {
+                yield_now().await;
+            }
+        }
+    }
+
+    /// An in-memory pipe that stands in for a UART
+    #[derive(Default)]
+    struct Pipe {
+        data: RefCell<VecDeque<u8>>,
+        waker: Cell<Option<Waker>>,
+        closed: Cell<bool>,
+    }
+
+    struct PipeReader<'a>(&'a Pipe);
+
+    /// The pipe is closed when the writer is dropped
+    struct PipeWriter<'a>(&'a Pipe);
+
+    impl embedded_io_async::ErrorType for PipeReader<'_> {
+        type Error = Infallible;
+    }
+
+    impl Read for PipeReader<'_> {
+        async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, Infallible> {
+            poll_fn(|context| {
+                let mut data = self.0.data.borrow_mut();
+
+                if data.is_empty() {
+                    if self.0.closed.get() {
+                        return Poll::Ready(Ok(0));
+                    }
+                    self.0.waker.set(Some(context.waker().clone()));
+                    return Poll::Pending;
+                }
+
+                // Give a few bytes at a time, like a UART would
+                let length = buffer.len().min(data.len()).min(7);
+                for (target, byte) in buffer.iter_mut().zip(data.drain(..length)) {
+                    *target = byte;
+                }
+                Poll::Ready(Ok(length))
+            })
+            .await
+        }
+    }
+
+    impl embedded_io_async::ErrorType for PipeWriter<'_> {
+        type Error = Infallible;
+    }
+
+    impl Write for PipeWriter<'_> {
+        async fn write(&mut self, buffer: &[u8]) -> Result<usize, Infallible> {
+            self.0.data.borrow_mut().extend(buffer);
+            if let Some(waker) = self.0.waker.take() {
+                waker.wake();
+            }
+            Ok(buffer.len())
+        }
+    }
+
+    impl Drop for PipeWriter<'_> {
+        fn drop(&mut self) {
+            self.0.closed.set(true);
+            if let Some(waker) = self.0.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// A modem that answers the commands it knows, until the pipe to it is closed
+    async fn modem(mut rx: PipeReader<'_>, mut tx: PipeWriter<'_>) {
+        let mut command = Vec::new();
+        let mut chunk = [0; 16];
+
+        loop {
+            let read = rx.read(&mut chunk).await.unwrap();
+            if read == 0 {
+                return;
+            }
+            command.extend_from_slice(&chunk[..read]);
+
+            // Answer every complete command, one at a time
+            while let Some(end) = command.windows(2).position(|bytes| bytes == b"\r\n") {
+                let answer: &[u8] = match &command[..end + 2] {
+                    b"AT+CSQ\r\n" => {
+                        b"AT+CSQ\r\r\nRING\r\n\r\n+CSQ: 20,99\r\n\r\nOK\r\n\r\nRING\r\n"
+                    }
+                    b"AT+CPIN?\r\n" => b"\r\n+CME ERROR: 10\r\n\r\n+CREG: 1\r\n",
+                    b"AT+CPBR=1,9\r\n" => b"\r\n+CPBR: 1,\"123\"\r\n+CPBR: 2,\"456\"\r\n\r\nOK\r\n",
+                    b"AT+CREG?\r\n" => b"\r\n+CREG: 0,1\r\n\r\nOK\r\n",
+                    b"AT+COPS?\r\n" => b"\r\n+COPS: 0\r\n\r\nOK\r\n",
+                    // Never answered
+                    b"AT+HANG\r\n" => b"",
+                    _ => b"\r\nERROR\r\n",
+                };
+                tx.write_all(answer).await.unwrap();
+                command.drain(..end + 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_send() {
+        let to_modem = Pipe::default();
+        let from_modem = Pipe::default();
+        let channel = AtChannel::<NoopRawMutex, 32>::new();
+        let (mut reader, mut handle) =
+            channel.split(PipeReader(&from_modem), PipeWriter(&to_modem), Turns, 1000);
+
+        let mut rings = 0;
+        let mut on_ring = |_: &[u8]| rings += 1;
+
+        let client = async move {
+            let response = handle.send(b"AT+CSQ\r\n").await.unwrap();
+            assert_eq!(
+                response,
+                Response::Complete {
+                    raw: b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n",
+                    result: FinalResult::Ok,
+                }
+            );
+
+            // The `+CREG` after the error isn't a URC, but nobody waits for it so it's dropped
+            let response = handle.send(b"AT+CPIN?\r\n").await.unwrap();
+            assert_eq!(
+                response.result(),
+                Some(FinalResult::CmeError(CmeError::Code(10)))
+            );
+
+            let response = handle.send(b"AT+FOO\r\n").await.unwrap();
+            assert_eq!(response.raw(), b"\r\nERROR\r\n");
+
+            assert_eq!(
+                handle.send(b"AT+CPBR=1,9\r\n").await,
+                Err(ClientError::Overflow)
+            );
+        };
+
+        {
+            let mut urcs = UrcDispatcher::<1>::new();
+            urcs.register(b"RING", &mut on_ring).unwrap();
+
+            let (reader_result, _, _) = block_on(join3(
+                reader.run(&mut urcs),
+                modem(PipeReader(&to_modem), PipeWriter(&from_modem)),
+                client,
+            ));
+            assert_eq!(reader_result, Ok(()));
+        }
+
+        assert_eq!(rings, 2);
+    }
+
+    #[test]
+    fn test_dropped_send() {
+        let to_modem = Pipe::default();
+        let from_modem = Pipe::default();
+        let channel = AtChannel::<NoopRawMutex, 64>::new();
+        let (mut reader, mut handle) =
+            channel.split(PipeReader(&from_modem), PipeWriter(&to_modem), Turns, 1000);
+
+        let mut registrations = 0;
+        let mut on_creg = |_: &[u8]| registrations += 1;
+
+        let client = async move {
+            // Stop waiting before the modem answered
+            let dropped = select(handle.send(b"AT+COPS?\r\n"), ready(())).await;
+            assert!(matches!(dropped, Either::Second(())));
+
+            // The late `+COPS` response doesn't end the response of the next command
+            let response = handle.send(b"AT+CPBR=1,9\r\n").await.unwrap();
+            assert_eq!(
+                response.raw(),
+                b"\r\n+CPBR: 1,\"123\"\r\n+CPBR: 2,\"456\"\r\n\r\nOK\r\n"
+            );
+
+            // A response that never comes is given up on when the next send is dropped too
+            let dropped = select(handle.send(b"AT+HANG\r\n"), ready(())).await;
+            assert!(matches!(dropped, Either::Second(())));
+            let dropped = select(handle.send(b"AT+CREG?\r\n"), ready(())).await;
+            assert!(matches!(dropped, Either::Second(())));
+
+            // The `+CREG:` lines are the response, not URCs
+            let response = handle.send(b"AT+CREG?\r\n").await.unwrap();
+            assert_eq!(response.raw(), b"\r\n+CREG: 0,1\r\n\r\nOK\r\n");
+        };
+
+        {
+            let mut urcs = UrcDispatcher::<1>::new();
+            urcs.register(b"+CREG:", &mut on_creg).unwrap();
+
+            let (reader_result, _, _) = block_on(join3(
+                reader.run(&mut urcs),
+                modem(PipeReader(&to_modem), PipeWriter(&from_modem)),
+                client,
+            ));
+            assert_eq!(reader_result, Ok(()));
+        }
+
+        assert_eq!(registrations, 0);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let to_modem = Pipe::default();
+        let from_modem = Pipe::default();
+        let channel = AtChannel::<NoopRawMutex, 64>::new();
+        let (mut reader, mut handle) =
+            channel.split(PipeReader(&from_modem), PipeWriter(&to_modem), Turns, 1000);
+
+        let client = async move {
+            assert_eq!(handle.send(b"AT+HANG\r\n").await, Err(ClientError::Timeout));
+
+            // The late response that never comes is waited for up to the timeout only
+            let response = handle.send(b"AT+CREG?\r\n").await.unwrap();
+            assert_eq!(response.raw(), b"\r\n+CREG: 0,1\r\n\r\nOK\r\n");
+
+            // Without a timeout, only a response that already came in would do
+            handle.set_timeout(0);
+            assert_eq!(
+                handle.send(b"AT+COPS?\r\n").await,
+                Err(ClientError::Timeout)
+            );
+        };
+
+        let mut urcs = UrcDispatcher::<0>::new();
+        let (reader_result, _, _) = block_on(join3(
+            reader.run(&mut urcs),
+            modem(PipeReader(&to_modem), PipeWriter(&from_modem)),
+            client,
+        ));
+        assert_eq!(reader_result, Ok(()));
+    }
+}
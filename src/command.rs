@@ -0,0 +1,332 @@
+//! Module that defines the trait that ties a command to its response
+
+use crate::parser::ParseError;
+
+/// Derive macro for [AtCommand].
+///
+/// ```rust
+/// use at_commands::command::AtCommand;
+///
+/// #[derive(AtCommand)]
+/// #[at(cmd = "+CGDCONT", kind = "set")]
+/// struct DefinePdpContext<'a> {
+///     cid: i32,
+///     pdp_type: &'a str,
+///     apn: Option<&'a str>,
+/// }
+///
+/// let mut buffer = [0; 128];
+/// let command = DefinePdpContext { cid: 1, pdp_type: "IP", apn: Some("internet") };
+///
+/// assert_eq!(command.write(&mut buffer).unwrap(), b"AT+CGDCONT=1,\"IP\",\"internet\"\r\n");
+/// ```
+///
+/// The struct needs an `#[at(cmd = "...", kind = "...")]` attribute.
+/// The kind is one of `test`, `query`, `set` or `execute`.
+///
+/// Only set commands can have fields. They become the parameters of the command in order of declaration:
+/// * Ints use [with_int_parameter_as](crate::builder::CommandBuilder::with_int_parameter_as)
+/// * `&str` uses [with_string_parameter](crate::builder::CommandBuilder::with_string_parameter)
+/// * `Option`s of those use the `with_optional_*` methods
+///
+/// A `#[at(raw)]` attribute on a string field makes it an unquoted parameter
+/// using [with_raw_parameter](crate::builder::CommandBuilder::with_raw_parameter).
+///
+/// The response type can be set with `#[at(response = "...")]` and must implement [AtResponse].
+/// A `'_` lifetime in the type refers to the lifetime of the response buffer.
+/// Without it, the response is `()` and expects the command to be answered with `\r\nOK\r\n`.
+#[cfg(feature = "derive")]
+pub use at_commands_derive::AtCommand;
+
+/// Derive macro for [AtResponse].
+///
+/// ```rust
+/// use at_commands::command::AtResponse;
+///
+/// #[derive(AtResponse)]
+/// #[at(prefix = "+CSQ:")]
+/// struct SignalQuality {
+///     rssi: i32,
+///     ber: i32,
+/// }
+///
+/// let response = SignalQuality::parse(b"+CSQ: 20,99\r\nOK\r\n").unwrap();
+///
+/// assert_eq!(response.rssi, 20);
+/// assert_eq!(response.ber, 99);
+/// ```
+///
+/// The struct needs an `#[at(prefix = "...")]` attribute with the identifier the response starts with.
+///
+/// The fields are parsed as the parameters after the prefix, in order of declaration:
+/// * Ints use [expect_int_parameter_as](crate::parser::CommandParser::expect_int_parameter_as)
+/// * `&str` uses [expect_string_parameter](crate::parser::CommandParser::expect_string_parameter)
+///   or [expect_raw_string](crate::parser::CommandParser::expect_raw_string) if the field has a `#[at(raw)]` attribute
+/// * `Option`s of those use the `expect_optional_*` methods
+///
+/// Because every field is taken out of the parser right away,
+/// there's no limit on the amount of fields like there is for the tuples of the parser.
+/// Anything after the last field, like the `OK`, is not checked.
+#[cfg(feature = "derive")]
+pub use at_commands_derive::AtResponse;
+
+/// # AtCommand
+/// A trait for typed AT commands
+///
+/// A command knows how to write itself using the [CommandBuilder](crate::builder::CommandBuilder)
+/// and how to parse the response the device sends back using the [CommandParser](crate::parser::CommandParser).
+/// This way the two can't drift apart and drivers can be generic over the commands they send.
+///
+/// ## Example
+/// ```rust
+/// use at_commands::builder::CommandBuilder;
+/// use at_commands::command::AtCommand;
+/// use at_commands::parser::{CommandParser, ParseError};
+///
+/// struct ReadGpio {
+///     pin: i32,
+/// }
+///
+/// impl AtCommand for ReadGpio {
+///     type Response<'a> = i32;
+///
+///     fn write<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b [u8], usize> {
+///         CommandBuilder::create_set(buffer, true)
+///             .named("+SYSGPIOREAD")
+///             .with_int_parameter(self.pin)
+///             .finish()
+///     }
+///
+///     fn parse_response<'a>(&self, buffer: &'a [u8]) -> Result<i32, ParseError<'a>> {
+///         let (level,) = CommandParser::parse(buffer)
+///             .expect_identifier(b"+SYSGPIOREAD:")
+///             .expect_int_parameter()
+///             .expect_identifier(b"\r\nOK\r\n")
+///             .finish()?;
+///         Ok(level)
+///     }
+/// }
+///
+/// let mut buffer = [0; 128];
+/// let command = ReadGpio { pin: 4 };
+///
+/// assert_eq!(command.write(&mut buffer).unwrap(), b"AT+SYSGPIOREAD=4\r\n");
+/// assert_eq!(command.parse_response(b"+SYSGPIOREAD:1\r\nOK\r\n"), Ok(1));
+/// ```
+pub trait AtCommand {
+    /// The type the response is parsed into. It may borrow from the response buffer.
+    type Response<'a>;
+
+    /// Writes the command into the buffer.
+    ///
+    /// This works the same as [CommandBuilder::finish](crate::builder::CommandBuilder::finish).
+    /// When Ok, it returns the slice of the buffer that contains the command.
+    /// If the buffer was not long enough,
+    /// then an Err is returned with the size that was required for it to succeed.
+    fn write<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b [u8], usize>;
+
+    /// Parses the response to this command.
+    fn parse_response<'a>(&self, buffer: &'a [u8]) -> Result<Self::Response<'a>, ParseError<'a>>;
+}
+
+/// # AtResponse
+/// A trait for responses that can be parsed on their own
+///
+/// This is what the [AtResponse derive macro](macro@AtResponse) implements.
+/// The lifetime is that of the buffer the response is parsed from, so the response can borrow from it.
+pub trait AtResponse<'a>: Sized {
+    /// Parses the response from the buffer
+    fn parse(buffer: &'a [u8]) -> Result<Self, ParseError<'a>>;
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+
+    #[derive(AtCommand)]
+    #[at(cmd = "+GMR", kind = "execute")]
+    struct Version;
+
+    #[derive(AtCommand)]
+    #[at(cmd = "+UART_CUR", kind = "query")]
+    struct UartConfig;
+
+    #[derive(AtCommand)]
+    #[at(cmd = "+CWMODE", kind = "test")]
+    struct WifiModes;
+
+    #[derive(AtCommand)]
+    #[at(cmd = "+HTTPCLIENT", kind = "set")]
+    struct HttpClient<'a> {
+        method: u8,
+        content_type: i32,
+        url: Option<&'a str>,
+        host: Option<&'a str>,
+        path: Option<&'a str>,
+        transport_type: Option<i16>,
+    }
+
+    #[derive(AtCommand)]
+    #[at(cmd = "+CWRECONNCFG", kind = "set")]
+    struct ReconnectConfig(i32, i32);
+
+    #[test]
+    fn test_derive_without_parameters() {
+        let mut buffer = [0; 128];
+
+        assert_eq!(Version.write(&mut buffer).unwrap(), b"AT+GMR\r\n");
+        assert_eq!(UartConfig.write(&mut buffer).unwrap(), b"AT+UART_CUR?\r\n");
+        assert_eq!(WifiModes.write(&mut buffer).unwrap(), b"AT+CWMODE=?\r\n");
+    }
+
+    #[test]
+    fn test_derive_with_parameters() {
+        let mut buffer = [0; 128];
+
+        let command = HttpClient {
+            method: 2,
+            content_type: 1,
+            url: Some("http://localpc/ip"),
+            host: None,
+            path: None,
+            transport_type: Some(1),
+        };
+        assert_eq!(
+            command.write(&mut buffer).unwrap(),
+            b"AT+HTTPCLIENT=2,1,\"http://localpc/ip\",,,1\r\n"
+        );
+
+        assert_eq!(
+            ReconnectConfig(1, 100).write(&mut buffer).unwrap(),
+            b"AT+CWRECONNCFG=1,100\r\n"
+        );
+    }
+
+    #[derive(AtResponse, Debug, PartialEq)]
+    #[at(prefix = "+CGDCONT:")]
+    struct PdpContext<'a> {
+        cid: i32,
+        pdp_type: &'a str,
+        apn: Option<&'a str>,
+        address: Option<&'a str>,
+        data_compression: Option<i32>,
+    }
+
+    #[derive(AtResponse, Debug, PartialEq)]
+    #[at(prefix = "+STATUS:")]
+    struct Status<'a>(#[at(raw)] &'a str);
+
+    #[derive(AtResponse, Debug, PartialEq)]
+    #[at(prefix = "+MANY:")]
+    struct ManyParameters {
+        p0: i32,
+        p1: i32,
+        p2: i32,
+        p3: i32,
+        p4: i32,
+        p5: i32,
+        p6: i32,
+        p7: i32,
+        p8: i32,
+        p9: i32,
+        p10: i32,
+        p11: i32,
+        p12: i32,
+        p13: i32,
+        p14: i32,
+        p15: i32,
+        p16: i32,
+        p17: i32,
+        p18: i32,
+        p19: i32,
+    }
+
+    #[derive(AtResponse, Debug, PartialEq)]
+    #[at(prefix = "+COUNTERS:")]
+    struct Counters {
+        cell_id: u32,
+        sent: u64,
+        offset: i64,
+        received: Option<u64>,
+    }
+
+    #[derive(AtCommand)]
+    #[at(cmd = "+TIME", kind = "set")]
+    struct SetTime(u64, Option<i64>);
+
+    #[derive(AtCommand)]
+    #[at(cmd = "+CGDCONT", kind = "query", response = "PdpContext<'_>")]
+    struct ReadPdpContext;
+
+    #[test]
+    fn test_derive_response_parse() {
+        assert_eq!(
+            PdpContext::parse(b"+CGDCONT: 1,\"IP\",\"internet\",,0\r\nOK\r\n"),
+            Ok(PdpContext {
+                cid: 1,
+                pdp_type: "IP",
+                apn: Some("internet"),
+                address: None,
+                data_compression: Some(0),
+            })
+        );
+        assert_eq!(
+            Status::parse(b"+STATUS: READY\r\nOK\r\n"),
+            Ok(Status("READY"))
+        );
+
+        let many = ManyParameters::parse(
+            b"+MANY:0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19\r\nOK\r\n",
+        )
+        .unwrap();
+        assert_eq!(many.p0, 0);
+        assert_eq!(many.p16, 16);
+        assert_eq!(many.p19, 19);
+
+        let error = PdpContext::parse(b"+CGDCONT: 1,2\r\nOK\r\n").unwrap_err();
+        assert_eq!(error.expected, crate::parser::Expected::StringParameter);
+        assert!(Status::parse(b"+CSQ: 20,99").is_err());
+    }
+
+    #[test]
+    fn test_derive_wide_ints() {
+        assert_eq!(
+            Counters::parse(b"+COUNTERS: 4294967295,18446744073709551615,-9000000000,\r\nOK\r\n"),
+            Ok(Counters {
+                cell_id: u32::MAX,
+                sent: u64::MAX,
+                offset: -9_000_000_000,
+                received: None,
+            })
+        );
+        assert_eq!(
+            Counters::parse(b"+COUNTERS: 4294967296,0,0,0\r\nOK\r\n")
+                .unwrap_err()
+                .kind,
+            crate::parser::ParseErrorKind::IntOverflow
+        );
+
+        let mut buffer = [0; 64];
+        assert_eq!(
+            SetTime(1_700_000_000_000, Some(-3600))
+                .write(&mut buffer)
+                .unwrap(),
+            b"AT+TIME=1700000000000,-3600\r\n"
+        );
+    }
+
+    #[test]
+    fn test_derive_command_with_response() {
+        let response = ReadPdpContext
+            .parse_response(b"+CGDCONT: 1,\"IP\",\"internet\"\r\nOK\r\n")
+            .unwrap();
+        assert_eq!(response.apn, Some("internet"));
+    }
+
+    #[test]
+    fn test_derive_response() {
+        assert_eq!(Version.parse_response(b"\r\nOK\r\n"), Ok(()));
+        assert!(Version.parse_response(b"\r\nERROR\r\n").is_err());
+        assert!(Version.parse_response(b"\r\n+CME ERROR: 10\r\n").is_err());
+    }
+}
@@ -0,0 +1,358 @@
+//! Module that parses the commands a device receives, for implementing the device side (DCE)
+
+use crate::builder::{Execute, Query, Set, Test};
+use crate::escape::Escaping;
+use crate::parser::{CommandParser, Expected, ParseError, ParseErrorKind};
+use crate::result_code::is_line_terminator;
+
+/// The kind of an incoming command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CommandKind {
+    /// A test command in the form `AT{name}=?`
+    Test(Test),
+    /// A query command in the form `AT{name}?`
+    Query(Query),
+    /// A set command in the form `AT{name}={param},{param},{param}`
+    Set(Set),
+    /// An execute command in the form `AT{name}`
+    Execute(Execute),
+}
+
+/// # IncomingCommand
+/// A command as received by a device
+///
+/// This is the reverse of the [CommandBuilder](crate::builder::CommandBuilder).
+///
+/// ## Example
+/// ```rust
+/// use at_commands::dce::{CommandKind, IncomingCommand};
+/// use at_commands::builder::Set;
+///
+/// let command = IncomingCommand::parse(b"AT+NAME=1,\"x\"\r").unwrap();
+///
+/// assert_eq!(command.name(), "+NAME");
+/// assert_eq!(command.kind(), CommandKind::Set(Set));
+///
+/// let mut parameters = command.parameters();
+/// assert_eq!(parameters.next(), Some(&b"1"[..]));
+/// assert_eq!(parameters.next(), Some(&b"\"x\""[..]));
+/// assert_eq!(parameters.next(), None);
+///
+/// let (number, name) = command
+///     .parameter_parser()
+///     .expect_int_parameter()
+///     .expect_string_parameter()
+///     .finish()
+///     .unwrap();
+///
+/// assert_eq!(number, 1);
+/// assert_eq!(name, "x");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IncomingCommand<'a> {
+    name: &'a str,
+    kind: CommandKind,
+    parameters: &'a [u8],
+}
+
+impl<'a> IncomingCommand<'a> {
+    /// Parses a command line.
+    ///
+    /// The line must start with `AT` (in any case) and may end with a line terminator.
+    /// A bare `AT` is an execute command with an empty name.
+    pub fn parse(buffer: &'a [u8]) -> Result<Self, ParseError<'a>> {
+        let error = |kind, expected, index| ParseError {
+            kind,
+            expected,
+            index,
+        };
+
+        match buffer.get(..2) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(b"AT") => {}
+            Some(_) => {
+                return Err(error(
+                    ParseErrorKind::IdentifierMismatch,
+                    Expected::Identifier(b"AT"),
+                    0,
+                ))
+            }
+            None => {
+                return Err(error(
+                    ParseErrorKind::UnexpectedEnd,
+                    Expected::Identifier(b"AT"),
+                    0,
+                ))
+            }
+        }
+
+        // The command line ends at the line terminator, if there is one
+        let line_end = buffer
+            .iter()
+            .position(|byte| is_line_terminator(*byte))
+            .unwrap_or(buffer.len());
+        let line = &buffer[..line_end];
+
+        let name_start = 2;
+        let name_end = name_start
+            + line[name_start..]
+                .iter()
+                .take_while(|byte| **byte != b'=' && **byte != b'?')
+                .count();
+
+        let name = match core::str::from_utf8(&line[name_start..name_end]) {
+            // A bare `AT` is a valid command
+            Ok("") if name_end == line.len() => "",
+            Ok("") => {
+                return Err(error(
+                    ParseErrorKind::IdentifierMismatch,
+                    Expected::CommandName,
+                    name_start,
+                ))
+            }
+            Ok(name) => name,
+            Err(_) => {
+                return Err(error(
+                    ParseErrorKind::InvalidUtf8,
+                    Expected::CommandName,
+                    name_start,
+                ))
+            }
+        };
+
+        let (kind, parameters) = match &line[name_end..] {
+            b"" => (CommandKind::Execute(Execute), &b""[..]),
+            b"?" => (CommandKind::Query(Query), &b""[..]),
+            b"=?" => (CommandKind::Test(Test), &b""[..]),
+            [b'=', parameters @ ..] => (CommandKind::Set(Set), parameters),
+            _ => {
+                return Err(error(
+                    ParseErrorKind::IdentifierMismatch,
+                    Expected::Identifier(b"="),
+                    name_end,
+                ))
+            }
+        };
+
+        Ok(Self {
+            name,
+            kind,
+            parameters,
+        })
+    }
+
+    /// The name of the command, like `+NAME`
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// The kind of the command
+    pub fn kind(&self) -> CommandKind {
+        self.kind
+    }
+
+    /// The text of all parameters as it was received
+    pub fn raw_parameters(&self) -> &'a [u8] {
+        self.parameters
+    }
+
+    /// An iterator over the raw parameters.
+    ///
+    /// String parameters still have their quotes and empty parameters are empty slices.
+    /// A string ends at its second quote, like it does for [CommandParser::expect_string_parameter].
+    pub fn parameters(&self) -> Parameters<'a> {
+        self.parameters_with(Escaping::None)
+    }
+
+    /// An iterator over the raw parameters of which the strings are escaped.
+    ///
+    /// An escaped quote doesn't end a string,
+    /// like it doesn't for [CommandParser::expect_escaped_string_parameter] with the same escaping.
+    /// The escape sequences are left in the parameters.
+    pub fn parameters_with(&self, escaping: Escaping) -> Parameters<'a> {
+        Parameters {
+            remaining: Some(self.parameters).filter(|parameters| !parameters.is_empty()),
+            escaping,
+        }
+    }
+
+    /// A [CommandParser] over the parameters.
+    ///
+    /// This reads the parameters with the same rules as responses are read.
+    /// The indices in its errors are relative to the start of the parameters.
+    pub fn parameter_parser(&self) -> CommandParser<'a, ()> {
+        CommandParser::parse(self.parameters)
+    }
+}
+
+/// Iterator over the raw parameters of an [IncomingCommand]
+#[derive(Debug, Clone)]
+pub struct Parameters<'a> {
+    remaining: Option<&'a [u8]>,
+    escaping: Escaping,
+}
+
+impl<'a> Iterator for Parameters<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.remaining?;
+
+        // Find the comma after the parameter, skipping the ones in strings
+        let mut in_string = false;
+        let mut escaped = false;
+        let end = remaining.iter().position(|byte| {
+            match byte {
+                _ if escaped => escaped = false,
+                _ if in_string && self.escaping.is_escape(*byte) => escaped = true,
+                b'"' => in_string = !in_string,
+                _ => {}
+            }
+            *byte == b',' && !in_string
+        });
+
+        match end {
+            Some(end) => {
+                self.remaining = Some(&remaining[end + 1..]);
+                Some(&remaining[..end])
+            }
+            None => {
+                self.remaining = None;
+                Some(remaining)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kinds() {
+        let command = IncomingCommand::parse(b"AT+TEST=?\r\n").unwrap();
+        assert_eq!(command.name(), "+TEST");
+        assert_eq!(command.kind(), CommandKind::Test(Test));
+        assert_eq!(command.parameters().next(), None);
+
+        let command = IncomingCommand::parse(b"AT+QUERY?\r").unwrap();
+        assert_eq!(command.name(), "+QUERY");
+        assert_eq!(command.kind(), CommandKind::Query(Query));
+
+        let command = IncomingCommand::parse(b"at+execute").unwrap();
+        assert_eq!(command.name(), "+execute");
+        assert_eq!(command.kind(), CommandKind::Execute(Execute));
+
+        let command = IncomingCommand::parse(b"AT\r").unwrap();
+        assert_eq!(command.name(), "");
+        assert_eq!(command.kind(), CommandKind::Execute(Execute));
+
+        let command = IncomingCommand::parse(b"AT+SET=\r").unwrap();
+        assert_eq!(command.kind(), CommandKind::Set(Set));
+        assert_eq!(command.raw_parameters(), b"");
+        assert_eq!(command.parameters().next(), None);
+    }
+
+    #[test]
+    fn test_parameters() {
+        let command =
+            IncomingCommand::parse(b"AT+HTTPCLIENT=2,1,\"http://host/a,b\",,,1\r").unwrap();
+        assert_eq!(command.name(), "+HTTPCLIENT");
+
+        let mut parameters = command.parameters();
+        assert_eq!(parameters.next(), Some(&b"2"[..]));
+        assert_eq!(parameters.next(), Some(&b"1"[..]));
+        assert_eq!(parameters.next(), Some(&b"\"http://host/a,b\""[..]));
+        assert_eq!(parameters.next(), Some(&b""[..]));
+        assert_eq!(parameters.next(), Some(&b""[..]));
+        assert_eq!(parameters.next(), Some(&b"1"[..]));
+        assert_eq!(parameters.next(), None);
+
+        let (method, content_type, url, host, path, transport) = command
+            .parameter_parser()
+            .expect_int_parameter()
+            .expect_int_parameter()
+            .expect_string_parameter()
+            .expect_optional_string_parameter()
+            .expect_optional_string_parameter()
+            .expect_optional_int_parameter()
+            .finish()
+            .unwrap();
+
+        assert_eq!((method, content_type), (2, 1));
+        assert_eq!(url, "http://host/a,b");
+        assert_eq!((host, path), (None, None));
+        assert_eq!(transport, Some(1));
+    }
+
+    #[test]
+    fn test_escaped_quote_parameters() {
+        // The iterator splits the parameters like the parser reads them
+        let command = IncomingCommand::parse(br#"AT+SEND="say \"a,b\"","c\\",1"#).unwrap();
+
+        let mut parameters = command.parameters_with(Escaping::Backslash);
+        assert_eq!(parameters.next(), Some(&br#""say \"a,b\"""#[..]));
+        assert_eq!(parameters.next(), Some(&br#""c\\""#[..]));
+        assert_eq!(parameters.next(), Some(&b"1"[..]));
+        assert_eq!(parameters.next(), None);
+
+        let (mut first, mut second) = ([0; 16], [0; 16]);
+        let (say, c, number) = command
+            .parameter_parser()
+            .expect_escaped_string_parameter(Escaping::Backslash, &mut first)
+            .expect_escaped_string_parameter(Escaping::Backslash, &mut second)
+            .expect_int_parameter()
+            .finish()
+            .unwrap();
+        assert_eq!((say, c, number), ("say \"a,b\"", "c\\", 1));
+
+        // Without escaping, a backslash is just a character
+        let command = IncomingCommand::parse(br#"AT+SEND="c\",1"#).unwrap();
+
+        let mut parameters = command.parameters();
+        assert_eq!(parameters.next(), Some(&br#""c\""#[..]));
+        assert_eq!(parameters.next(), Some(&b"1"[..]));
+        assert_eq!(parameters.next(), None);
+
+        let (c, number) = command
+            .parameter_parser()
+            .expect_string_parameter()
+            .expect_int_parameter()
+            .finish()
+            .unwrap();
+        assert_eq!((c, number), ("c\\", 1));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            IncomingCommand::parse(b"+CSQ\r"),
+            Err(ParseError {
+                kind: ParseErrorKind::IdentifierMismatch,
+                expected: Expected::Identifier(b"AT"),
+                index: 0,
+            })
+        );
+        assert_eq!(
+            IncomingCommand::parse(b"A").unwrap_err().kind,
+            ParseErrorKind::UnexpectedEnd
+        );
+        assert_eq!(
+            IncomingCommand::parse(b"AT=1\r"),
+            Err(ParseError {
+                kind: ParseErrorKind::IdentifierMismatch,
+                expected: Expected::CommandName,
+                index: 2,
+            })
+        );
+        assert_eq!(
+            IncomingCommand::parse(b"AT+CSQ?1\r"),
+            Err(ParseError {
+                kind: ParseErrorKind::IdentifierMismatch,
+                expected: Expected::Identifier(b"="),
+                index: 6,
+            })
+        );
+    }
+}
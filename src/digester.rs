@@ -0,0 +1,410 @@
+//! Module that splits a stream of bytes from a device into the parts of its responses
+
+use crate::response_builder::ResultCodeFormat;
+use crate::result_code::{is_line_terminator, FinalResult};
+
+/// A complete part of the byte stream of a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DigestItem<'a> {
+    /// The echo of a command that was sent to the device, without the line terminator
+    Echo(&'a [u8]),
+    /// The `>` prompt with which the device asks for data
+    Prompt,
+    /// A line of information text or an unsolicited result code, without the line terminator
+    Line(&'a [u8]),
+    /// The final result code that ends a response
+    FinalResult(FinalResult<'a>),
+    /// The buffer filled up without a complete line in it.
+    /// The data is given back once and then dropped,
+    /// together with the rest of the line when it comes in.
+    Overflow(&'a [u8]),
+}
+
+/// Digests the first item in the buffer.
+///
+/// Returns the item if the buffer contains a complete one and the amount of bytes that were consumed.
+/// The consumed bytes can be dropped from the buffer, even if no item was found.
+/// That way this can be used on any buffer, like the contiguous part of a ring buffer.
+///
+/// The bytes of an item are the whitespace and empty lines before it, the item and its line terminator.
+///
+/// ```rust
+/// use at_commands::digester::{digest, DigestItem};
+/// use at_commands::result_code::FinalResult;
+///
+/// let buffer = b"AT+CSQ\r\r\n+CSQ: 20,99\r\n\r\nOK\r\n";
+///
+/// let (item, consumed) = digest(buffer);
+/// assert_eq!(item, Some(DigestItem::Echo(b"AT+CSQ")));
+///
+/// let buffer = &buffer[consumed..];
+/// let (item, consumed) = digest(buffer);
+/// assert_eq!(item, Some(DigestItem::Line(b"+CSQ: 20,99")));
+///
+/// let buffer = &buffer[consumed..];
+/// let (item, _) = digest(buffer);
+/// assert_eq!(item, Some(DigestItem::FinalResult(FinalResult::Ok)));
+/// ```
+pub fn digest(buffer: &[u8]) -> (Option<DigestItem<'_>>, usize) {
+    digest_with(buffer, ResultCodeFormat::default())
+}
+
+/// Digests the first item in the buffer, recognizing the final result codes of the given format.
+///
+/// See [digest] and [FinalResult::parse_line_with].
+pub fn digest_with(buffer: &[u8], format: ResultCodeFormat) -> (Option<DigestItem<'_>>, usize) {
+    // Skip the whitespace between the items
+    let start = buffer
+        .iter()
+        .take_while(|byte| is_line_terminator(**byte) || **byte == b' ')
+        .count();
+    let remaining = &buffer[start..];
+
+    if remaining.first() == Some(&b'>') {
+        // The prompt is not followed by a line terminator, but usually by a space
+        let space = (remaining.get(1) == Some(&b' ')) as usize;
+        return (Some(DigestItem::Prompt), start + 1 + space);
+    }
+
+    let line_length = match remaining.iter().position(|byte| is_line_terminator(*byte)) {
+        Some(line_length) => line_length,
+        // The line is not complete yet
+        None => return (None, start),
+    };
+    let line = &remaining[..line_length];
+
+    // Consume the line and its terminator
+    let terminator_length = match &remaining[line_length..] {
+        [b'\r', b'\n', ..] => 2,
+        // In verbose mode the lines end with `\r\n`, so the `\n` is still to come.
+        // (Only a numeric result code ends with just `\r`.)
+        [b'\r'] if format.verbose => return (None, start),
+        _ => 1,
+    };
+    let consumed = start + line_length + terminator_length;
+
+    let item = if line.starts_with(b"AT") || line.starts_with(b"at") {
+        DigestItem::Echo(line)
+    } else if let Some(result) = FinalResult::parse_line_with(line, format) {
+        DigestItem::FinalResult(result)
+    } else {
+        DigestItem::Line(line)
+    };
+
+    (Some(item), consumed)
+}
+
+/// # Digester
+/// Collects the bytes of a device and splits them into [DigestItem]s
+///
+/// The bytes can come in chunks of any size, like the ones a UART DMA gives.
+/// Everything is stored in a fixed-size buffer of `N` bytes, so nothing is allocated.
+/// The buffer must be big enough to contain the longest line the device sends.
+///
+/// The final result codes are expected in the verbose format of `ATV1`.
+/// For a device that is set to the numeric format, use [Self::set_result_code_format].
+///
+/// ## Example
+/// ```rust
+/// use at_commands::digester::{DigestItem, Digester};
+/// use at_commands::result_code::FinalResult;
+///
+/// let mut digester = Digester::<64>::new();
+///
+/// digester.feed(b"\r\n+CSQ: 2");
+/// assert_eq!(digester.next_item(), None);
+///
+/// digester.feed(b"0,99\r\n\r\nOK\r\n");
+/// assert_eq!(digester.next_item(), Some(DigestItem::Line(b"+CSQ: 20,99")));
+/// assert_eq!(
+///     digester.next_item(),
+///     Some(DigestItem::FinalResult(FinalResult::Ok))
+/// );
+/// assert_eq!(digester.next_item(), None);
+/// ```
+pub struct Digester<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+    /// The amount of bytes of the last returned item that still need to be removed
+    consumed: usize,
+    /// True after an overflow, until the end of the line that didn't fit has been dropped
+    discarding: bool,
+    format: ResultCodeFormat,
+}
+
+impl<const N: usize> Digester<N> {
+    /// Creates a new empty digester
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; N],
+            len: 0,
+            consumed: 0,
+            discarding: false,
+            format: ResultCodeFormat {
+                verbose: true,
+                quiet: false,
+            },
+        }
+    }
+
+    /// Sets the format of the final result codes the device sends, like it was set with `ATV`
+    pub fn set_result_code_format(&mut self, format: ResultCodeFormat) {
+        self.format = format;
+    }
+
+    /// Adds bytes to the buffer.
+    ///
+    /// Returns the amount of bytes that were taken.
+    /// If the buffer is full, not all bytes are taken
+    /// and the rest must be fed again after items have been taken out with [Self::next_item].
+    pub fn feed(&mut self, data: &[u8]) -> usize {
+        self.remove_consumed();
+
+        let free = &mut self.buffer[self.len..];
+        let taken = free.len().min(data.len());
+        for (buffer, data) in free.iter_mut().zip(&data[..taken]) {
+            *buffer = *data;
+        }
+        self.len += taken;
+
+        taken
+    }
+
+    /// Gets the next complete item out of the buffer, if there is one.
+    ///
+    /// The item is removed from the buffer the next time the digester is used.
+    pub fn next_item(&mut self) -> Option<DigestItem<'_>> {
+        self.next_item_with_bytes().map(|(item, _)| item)
+    }
+
+    /// Gets the next complete item out of the buffer together with the bytes it was digested from.
+    ///
+    /// The bytes are what the device sent for the item, including the whitespace and empty lines before it
+    /// and its line terminator.
+    /// So the bytes of the items one after another are the byte stream as it came in,
+    /// except for the rest of a line that overflowed.
+    ///
+    /// ```rust
+    /// use at_commands::digester::{DigestItem, Digester};
+    ///
+    /// let mut digester = Digester::<64>::new();
+    ///
+    /// digester.feed(b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n");
+    /// assert_eq!(
+    ///     digester.next_item_with_bytes(),
+    ///     Some((DigestItem::Line(b"+CSQ: 20,99"), &b"\r\n+CSQ: 20,99\r\n"[..]))
+    /// );
+    /// ```
+    pub fn next_item_with_bytes(&mut self) -> Option<(DigestItem<'_>, &[u8])> {
+        self.remove_consumed();
+
+        if self.discarding {
+            // Drop the rest of the line that overflowed, but keep its terminator
+            match self.buffer[..self.len]
+                .iter()
+                .position(|byte| is_line_terminator(*byte))
+            {
+                Some(end) => {
+                    self.consumed = end;
+                    self.discarding = false;
+                }
+                None => self.consumed = self.len,
+            }
+            self.remove_consumed();
+        }
+
+        let (item, consumed) = digest_with(&self.buffer[..self.len], self.format);
+
+        match item {
+            Some(item) => {
+                self.consumed = consumed;
+                Some((item, &self.buffer[..consumed]))
+            }
+            // The whitespace is kept for the bytes of the next item, unless room is needed
+            None if self.len < N => None,
+            None if consumed > 0 => {
+                self.consumed = consumed;
+                None
+            }
+            None => {
+                self.consumed = N;
+                self.discarding = true;
+                Some((DigestItem::Overflow(&self.buffer), &self.buffer))
+            }
+        }
+    }
+
+    /// Returns the bytes that are in the buffer and have not been digested yet
+    pub fn pending(&self) -> &[u8] {
+        &self.buffer[self.consumed..self.len]
+    }
+
+    /// Drops everything in the buffer
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.consumed = 0;
+        self.discarding = false;
+    }
+
+    fn remove_consumed(&mut self) {
+        self.buffer.copy_within(self.consumed..self.len, 0);
+        self.len -= self.consumed;
+        self.consumed = 0;
+    }
+}
+
+impl<const N: usize> Default for Digester<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result_code::CmeError;
+
+    #[test]
+    fn test_digest() {
+        assert_eq!(digest(b""), (None, 0));
+        assert_eq!(digest(b"\r\n\r\n"), (None, 4));
+        assert_eq!(digest(b"\r\n+CSQ: 20"), (None, 2));
+        assert_eq!(digest(b"\r\n> "), (Some(DigestItem::Prompt), 4));
+        assert_eq!(digest(b"\r\n>"), (Some(DigestItem::Prompt), 3));
+        assert_eq!(
+            digest(b"ATE0\r\r\nOK\r\n"),
+            (Some(DigestItem::Echo(b"ATE0")), 5)
+        );
+        assert_eq!(
+            digest(b"\r\n+CME ERROR: 10\r\n"),
+            (
+                Some(DigestItem::FinalResult(FinalResult::CmeError(
+                    CmeError::Code(10)
+                ))),
+                18
+            )
+        );
+        assert_eq!(
+            digest(b"\r\n+CREG: 1\r\n"),
+            (Some(DigestItem::Line(b"+CREG: 1")), 12)
+        );
+        // The `\n` after the `\r` is waited for, unless the result code is numeric
+        assert_eq!(digest(b"\r\n+CREG: 1\r"), (None, 2));
+        assert_eq!(
+            digest(b"\r\n+CREG: 1\n"),
+            (Some(DigestItem::Line(b"+CREG: 1")), 11)
+        );
+        assert_eq!(
+            digest_with(
+                b"0\r",
+                ResultCodeFormat {
+                    verbose: false,
+                    quiet: false,
+                }
+            ),
+            (Some(DigestItem::FinalResult(FinalResult::Ok)), 2)
+        );
+    }
+
+    #[test]
+    fn test_digester_byte_by_byte() {
+        let stream = b"AT+CGMR\r\r\n+CGMR: 1.0\r\n\r\nOK\r\n\r\nRING\r\nAT+CMGS=\"123\"\r\r\n> ";
+        let mut digester = Digester::<32>::new();
+        let mut items = 0;
+
+        for byte in stream {
+            assert_eq!(digester.feed(&[*byte]), 1);
+
+            if let Some(item) = digester.next_item() {
+                let expected = match items {
+                    0 => DigestItem::Echo(b"AT+CGMR"),
+                    1 => DigestItem::Line(b"+CGMR: 1.0"),
+                    2 => DigestItem::FinalResult(FinalResult::Ok),
+                    3 => DigestItem::Line(b"RING"),
+                    4 => DigestItem::Echo(b"AT+CMGS=\"123\""),
+                    5 => DigestItem::Prompt,
+                    _ => panic!("Too many items"),
+                };
+                assert_eq!(item, expected);
+                items += 1;
+            }
+        }
+
+        assert_eq!(items, 6);
+        assert_eq!(digester.next_item(), None);
+    }
+
+    #[test]
+    fn test_digester_result_code_format() {
+        // A message text that is just a number is not a final result code in verbose mode
+        let mut digester = Digester::<64>::new();
+        digester.feed(b"\r\n+CMGR: \"REC READ\",\"123\"\r\n4\r\n0\r\n\r\nOK\r\n");
+        assert_eq!(
+            digester.next_item(),
+            Some(DigestItem::Line(b"+CMGR: \"REC READ\",\"123\""))
+        );
+        assert_eq!(digester.next_item(), Some(DigestItem::Line(b"4")));
+        assert_eq!(digester.next_item(), Some(DigestItem::Line(b"0")));
+        assert_eq!(
+            digester.next_item(),
+            Some(DigestItem::FinalResult(FinalResult::Ok))
+        );
+
+        digester.set_result_code_format(ResultCodeFormat {
+            verbose: false,
+            quiet: false,
+        });
+        digester.feed(b"+CSQ: 20,99\r\n0\r");
+        assert_eq!(digester.next_item(), Some(DigestItem::Line(b"+CSQ: 20,99")));
+        assert_eq!(
+            digester.next_item(),
+            Some(DigestItem::FinalResult(FinalResult::Ok))
+        );
+    }
+
+    #[test]
+    fn test_digester_bytes() {
+        // Binary data with line terminators in it, from `AT+QIRD`
+        let stream = b"AT+QIRD=0\r\r\n+QIRD: 4\r\n\n\r\x00\r\r\n\r\nOK\r\n> ";
+        let mut digester = Digester::<64>::new();
+        let mut bytes = Vec::new();
+
+        for chunk in stream.chunks(3) {
+            digester.feed(chunk);
+            while let Some((_, item_bytes)) = digester.next_item_with_bytes() {
+                bytes.extend_from_slice(item_bytes);
+            }
+        }
+        assert_eq!(bytes, stream);
+    }
+
+    #[test]
+    fn test_digester_full() {
+        let mut digester = Digester::<8>::new();
+
+        assert_eq!(digester.feed(b"\r\n+CGMR: 1.0\r\n"), 8);
+        assert_eq!(digester.next_item(), None);
+        assert_eq!(digester.pending(), b"+CGMR:");
+
+        assert_eq!(digester.feed(b" 1.0\r\n"), 2);
+        assert_eq!(
+            digester.next_item(),
+            Some(DigestItem::Overflow(b"+CGMR: 1"))
+        );
+        assert_eq!(digester.next_item(), None);
+
+        // The rest of the line that overflowed is dropped
+        assert_eq!(digester.feed(b".0"), 2);
+        assert_eq!(digester.next_item(), None);
+        assert_eq!(digester.feed(b"\r\nOK\r\n"), 6);
+        assert_eq!(
+            digester.next_item(),
+            Some(DigestItem::FinalResult(FinalResult::Ok))
+        );
+
+        digester.feed(b"+CGMR");
+        digester.clear();
+        assert_eq!(digester.pending(), b"");
+    }
+}
@@ -0,0 +1,180 @@
+//! Module that escapes and unescapes the contents of string parameters
+
+use crate::formatter::{hex_byte, hex_value};
+use crate::parser::ParseErrorKind;
+
+/// How the special characters in a string parameter are escaped
+///
+/// ```rust
+/// use at_commands::builder::CommandBuilder;
+/// use at_commands::escape::Escaping;
+///
+/// let mut buffer = [0; 128];
+///
+/// let result = CommandBuilder::create_set(&mut buffer, true)
+///     .named("+CWJAP")
+///     .with_escaped_string_parameter("my,ssid", Escaping::Backslash)
+///     .with_escaped_string_parameter("pass\"word", Escaping::Backslash)
+///     .finish()
+///     .unwrap();
+///
+/// assert_eq!(result, b"AT+CWJAP=\"my\\,ssid\",\"pass\\\"word\"\r\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Escaping {
+    /// The string is used verbatim, so it can't contain a `"`
+    #[default]
+    None,
+    /// The backslash escaping that ESP-AT uses.
+    ///
+    /// A `"`, `\` or `,` is preceded by a `\`.
+    Backslash,
+    /// The hex escaping of V.250.
+    ///
+    /// A `"`, `\` or control character is written as a `\` followed by its value as two hex digits, like `\22`.
+    Hex,
+}
+
+impl Escaping {
+    /// Returns true if the byte starts an escape sequence
+    pub(crate) fn is_escape(self, byte: u8) -> bool {
+        self != Escaping::None && byte == b'\\'
+    }
+
+    /// Escapes the value and gives the pieces of the result to `write`
+    pub(crate) fn escape(self, value: &[u8], mut write: impl FnMut(&[u8])) {
+        // Write as many bytes at once as possible
+        let mut unwritten = 0;
+
+        for (index, byte) in value.iter().enumerate() {
+            let needs_escape = match self {
+                Escaping::None => false,
+                Escaping::Backslash => matches!(byte, b'"' | b'\\' | b','),
+                Escaping::Hex => matches!(byte, b'"' | b'\\') || byte.is_ascii_control(),
+            };
+
+            if !needs_escape {
+                continue;
+            }
+
+            write(&value[unwritten..index]);
+            match self {
+                Escaping::Hex => {
+                    let [high, low] = hex_byte(*byte, true);
+                    write(&[b'\\', high, low])
+                }
+                _ => write(&[b'\\', *byte]),
+            }
+            unwritten = index + 1;
+        }
+
+        write(&value[unwritten..]);
+    }
+
+    /// Unescapes the value into the buffer.
+    ///
+    /// Returns the part of the buffer that was filled.
+    pub(crate) fn unescape<'b>(
+        self,
+        value: &[u8],
+        buffer: &'b mut [u8],
+    ) -> Result<&'b [u8], ParseErrorKind> {
+        let mut length = 0;
+        let mut remaining = value;
+
+        loop {
+            let (byte, rest) = match (self, remaining) {
+                (_, []) => break,
+                (Escaping::Backslash, [b'\\', byte, rest @ ..]) => (*byte, rest),
+                (Escaping::Hex, [b'\\', high, low, rest @ ..]) => {
+                    match (hex_value(*high), hex_value(*low)) {
+                        (Some(high), Some(low)) => ((high << 4) | low, rest),
+                        _ => return Err(ParseErrorKind::InvalidEscape),
+                    }
+                }
+                (Escaping::Backslash | Escaping::Hex, [b'\\', ..]) => {
+                    return Err(ParseErrorKind::InvalidEscape)
+                }
+                (_, [byte, rest @ ..]) => (*byte, rest),
+            };
+
+            match buffer.get_mut(length) {
+                Some(target) => *target = byte,
+                None => return Err(ParseErrorKind::BufferTooSmall),
+            }
+            length += 1;
+            remaining = rest;
+        }
+
+        Ok(&buffer[..length])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escape<'b>(escaping: Escaping, value: &[u8], buffer: &'b mut [u8]) -> &'b [u8] {
+        let mut length = 0;
+        escaping.escape(value, |data| {
+            buffer[length..length + data.len()].copy_from_slice(data);
+            length += data.len();
+        });
+        &buffer[..length]
+    }
+
+    #[test]
+    fn test_escape() {
+        let mut buffer = [0; 64];
+
+        assert_eq!(
+            escape(Escaping::None, b"a\"b\\c,d", &mut buffer),
+            b"a\"b\\c,d"
+        );
+        assert_eq!(
+            escape(Escaping::Backslash, b"a\"b\\c,d", &mut buffer),
+            b"a\\\"b\\\\c\\,d"
+        );
+        assert_eq!(
+            escape(Escaping::Hex, b"a\"b\\c,d\r\n", &mut buffer),
+            b"a\\22b\\5Cc,d\\0D\\0A"
+        );
+        assert_eq!(escape(Escaping::Hex, b"", &mut buffer), b"");
+    }
+
+    #[test]
+    fn test_unescape() {
+        let mut buffer = [0; 64];
+
+        assert_eq!(
+            Escaping::Backslash.unescape(b"a\\\"b\\\\c\\,d", &mut buffer),
+            Ok(&b"a\"b\\c,d"[..])
+        );
+        assert_eq!(
+            Escaping::Hex.unescape(b"a\\22b\\5cc,d\\0D\\0A", &mut buffer),
+            Ok(&b"a\"b\\c,d\r\n"[..])
+        );
+        assert_eq!(
+            Escaping::None.unescape(b"a\\22", &mut buffer),
+            Ok(&b"a\\22"[..])
+        );
+
+        assert_eq!(
+            Escaping::Backslash.unescape(b"abc\\", &mut buffer),
+            Err(ParseErrorKind::InvalidEscape)
+        );
+        assert_eq!(
+            Escaping::Hex.unescape(b"\\2G", &mut buffer),
+            Err(ParseErrorKind::InvalidEscape)
+        );
+        assert_eq!(
+            Escaping::Hex.unescape(b"\\2", &mut buffer),
+            Err(ParseErrorKind::InvalidEscape)
+        );
+        assert_eq!(
+            Escaping::Backslash.unescape(b"abc", &mut buffer[..2]),
+            Err(ParseErrorKind::BufferTooSmall)
+        );
+    }
+}
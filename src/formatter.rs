@@ -1,6 +1,243 @@
 //! This module contains some helper functions to avoid having to call into the expensive fmt code.
 
-pub const MAX_INT_DIGITS: usize = 11;
+use crate::parser::ParseErrorKind;
+
+/// The maximum amount of characters an integer can take up.
+///
+/// This is the length of `-9223372036854775808` and `18446744073709551615`.
+pub const MAX_INT_DIGITS: usize = 20;
+
+/// The maximum amount of hex digits an integer can take up
+pub const MAX_HEX_DIGITS: usize = 16;
+
+const HEX_DIGITS_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+const HEX_DIGITS_LOWER: &[u8; 16] = b"0123456789abcdef";
+
+/// An integer type that can be used as a parameter.
+///
+/// It is implemented for `i8`, `i16`, `i32`, `i64`, `u8`, `u16`, `u32` and `u64`.
+pub trait Integer: private::Sealed {}
+
+pub(crate) mod private {
+    use super::ParseErrorKind;
+
+    pub trait Sealed: Copy + Default {
+        /// Writes the value to the buffer, see [super::write_int]
+        fn write(self, buffer: &mut [u8]) -> usize;
+        /// Parses the digits with an optional leading minus, see [super::parse_int]
+        fn parse(buffer: &[u8]) -> Result<Self, ParseErrorKind>;
+        /// Writes the bits of the value as hex digits in reverse, see [super::write_hex]
+        fn write_hex(self, buffer: &mut [u8], uppercase: bool) -> usize;
+        /// Parses hex digits into the bits of the value, see [super::parse_hex]
+        fn parse_hex(buffer: &[u8]) -> Result<Self, ParseErrorKind>;
+        /// Converts the value to a length, if it fits
+        fn to_usize(self) -> Option<usize>;
+    }
+}
+
+macro_rules! impl_signed_integer {
+    ($($int:ty => $unsigned:ty),*) => {$(
+        impl Integer for $int {}
+        impl private::Sealed for $int {
+            fn write(mut self, buffer: &mut [u8]) -> usize {
+                let mut buffer_index = 0;
+                let is_negative = self.is_negative();
+
+                // We want a negative value because that can hold every absolute value.
+                if !is_negative {
+                    self = -self;
+                }
+
+                // Special case for 0
+                if self == 0 {
+                    buffer[buffer_index] = b'0';
+                    buffer_index += 1;
+                }
+
+                // Write the smallest digit to the buffer.
+                // This will put it in there in reverse.
+                while self != 0 {
+                    // The value is negative, so invert the smallest digit, offset it with the 0 character
+                    // and put it in the buffer.
+                    buffer[buffer_index] = b'0' + -(self % 10) as u8;
+                    buffer_index += 1;
+                    // Divide the value to get rid of the smallest digit.
+                    self /= 10;
+                }
+
+                if is_negative {
+                    // Don't forget to put the minus sign there.
+                    buffer[buffer_index] = b'-';
+                    buffer_index += 1;
+                }
+
+                buffer_index
+            }
+
+            fn parse(mut buffer: &[u8]) -> Result<Self, ParseErrorKind> {
+                let is_negative = buffer.first() == Some(&b'-');
+
+                if is_negative {
+                    buffer = &buffer[1..];
+                }
+
+                if buffer.is_empty() {
+                    return Err(ParseErrorKind::InvalidInt);
+                }
+
+                // The value is built up negative because that can hold every absolute value.
+                let mut value: $int = 0;
+                for char in buffer.iter() {
+                    if !char.is_ascii_digit() {
+                        return Err(ParseErrorKind::InvalidInt);
+                    }
+                    value = value
+                        .checked_mul(10)
+                        .and_then(|value| value.checked_sub((*char - b'0') as $int))
+                        .ok_or(ParseErrorKind::IntOverflow)?;
+                }
+
+                if is_negative {
+                    Ok(value)
+                } else {
+                    value.checked_neg().ok_or(ParseErrorKind::IntOverflow)
+                }
+            }
+
+            fn write_hex(self, buffer: &mut [u8], uppercase: bool) -> usize {
+                // Hex shows the bits, so the value is written as its two's complement
+                (self as $unsigned).write_hex(buffer, uppercase)
+            }
+
+            fn parse_hex(buffer: &[u8]) -> Result<Self, ParseErrorKind> {
+                <$unsigned>::parse_hex(buffer).map(|value| value as $int)
+            }
+
+            fn to_usize(self) -> Option<usize> {
+                usize::try_from(self).ok()
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_unsigned_integer {
+    ($($int:ty),*) => {$(
+        impl Integer for $int {}
+        impl private::Sealed for $int {
+            fn write(mut self, buffer: &mut [u8]) -> usize {
+                let mut buffer_index = 0;
+
+                // Special case for 0
+                if self == 0 {
+                    buffer[buffer_index] = b'0';
+                    buffer_index += 1;
+                }
+
+                // Write the smallest digit to the buffer.
+                // This will put it in there in reverse.
+                while self != 0 {
+                    buffer[buffer_index] = b'0' + (self % 10) as u8;
+                    buffer_index += 1;
+                    // Divide the value to get rid of the smallest digit.
+                    self /= 10;
+                }
+
+                buffer_index
+            }
+
+            fn parse(buffer: &[u8]) -> Result<Self, ParseErrorKind> {
+                if buffer.is_empty() {
+                    return Err(ParseErrorKind::InvalidInt);
+                }
+
+                let mut value: $int = 0;
+                for char in buffer.iter() {
+                    if !char.is_ascii_digit() {
+                        return Err(ParseErrorKind::InvalidInt);
+                    }
+                    value = value
+                        .checked_mul(10)
+                        .and_then(|value| value.checked_add((*char - b'0') as $int))
+                        .ok_or(ParseErrorKind::IntOverflow)?;
+                }
+
+                Ok(value)
+            }
+
+            fn write_hex(mut self, buffer: &mut [u8], uppercase: bool) -> usize {
+                let digits = if uppercase {
+                    HEX_DIGITS_UPPER
+                } else {
+                    HEX_DIGITS_LOWER
+                };
+                let mut buffer_index = 0;
+
+                // Write the smallest digit to the buffer.
+                // This will put it in there in reverse.
+                // Zero still gets one digit.
+                loop {
+                    buffer[buffer_index] = digits[(self & 0xF) as usize];
+                    buffer_index += 1;
+                    self >>= 4;
+
+                    if self == 0 {
+                        break;
+                    }
+                }
+
+                buffer_index
+            }
+
+            fn parse_hex(buffer: &[u8]) -> Result<Self, ParseErrorKind> {
+                if buffer.is_empty() {
+                    return Err(ParseErrorKind::InvalidInt);
+                }
+
+                let mut value: $int = 0;
+                for char in buffer.iter() {
+                    let digit = hex_value(*char).ok_or(ParseErrorKind::InvalidInt)?;
+                    value = value
+                        .checked_mul(16)
+                        .map(|value| value | digit as $int)
+                        .ok_or(ParseErrorKind::IntOverflow)?;
+                }
+
+                Ok(value)
+            }
+
+            fn to_usize(self) -> Option<usize> {
+                usize::try_from(self).ok()
+            }
+        }
+    )*};
+}
+
+impl_signed_integer!(i8 => u8, i16 => u16, i32 => u32, i64 => u64);
+impl_unsigned_integer!(u8, u16, u32, u64);
+
+/// Copies the data into the buffer at the index, if it fits.
+///
+/// If it won't fit, it silently fails and won't copy the data.
+/// Returns the index after the data, no matter if it was copied or not.
+/// That way a builder can report how big its buffer needs to be.
+pub fn try_append_data(buffer: &mut [u8], index: usize, data: &[u8]) -> usize {
+    let data_length = data.len();
+
+    // Why not just use copy_from_slice?
+    // That can give a panic and thus dumps a lot of fmt code in the binary.
+    // The compiler can check every aspect of this and so the code will never panic.
+
+    // Does the buffer have enough space left?
+    if let Some(buffer_slice) = buffer.get_mut(index..(index + data_length)) {
+        // Yes, zip the buffer with the data
+        for (buffer, data) in buffer_slice.iter_mut().zip(data) {
+            // Copy over the bytes.
+            *buffer = *data;
+        }
+    }
+
+    index + data_length
+}
 
 /// Writes ascii bytes to the buffer to represent the given int value.
 ///
@@ -8,41 +245,38 @@ pub const MAX_INT_DIGITS: usize = 11;
 /// It can be used as a value or to determine the length of the formatting.
 ///
 /// Panics if the buffer is less than [MAX_INT_DIGITS] long.
-pub fn write_int(buffer: &mut [u8], mut value: i32) -> &mut [u8] {
+pub fn write_int<I: Integer>(buffer: &mut [u8], value: I) -> &mut [u8] {
     // Check in debug mode if the buffer is long enough.
     // We don't do this in release to have less overhead.
     debug_assert!(buffer.len() >= MAX_INT_DIGITS);
 
-    let mut buffer_index = 0;
-    let is_negative = value.is_negative();
+    let buffer_index = value.write(buffer);
 
-    // We want a negative value because that can hold every absolute value.
-    if !is_negative {
-        value = -value;
-    }
+    // We built the buffer in reverse, so now we've got to undo that.
+    buffer[0..buffer_index].reverse();
 
-    // Special case for 0
-    if value == 0 {
-        buffer[buffer_index] = b'0';
-        buffer_index += 1;
-    }
+    &mut buffer[0..buffer_index]
+}
 
-    // Write the smallest digit to the buffer.
-    // This will put it in there in reverse.
-    while value != 0 {
-        // The value is negative, so invert the smallest digit, offset it with the 0 character
-        // and put it in the buffer.
-        buffer[buffer_index] = b'0' + -(value % 10) as u8;
-        buffer_index += 1;
-        // Divide the value to get rid of the smallest digit.
-        value /= 10;
-    }
+/// Parses an int.
+///
+/// Values that don't fit in the type are an [ParseErrorKind::IntOverflow] error.
+pub fn parse_int<I: Integer>(buffer: &[u8]) -> Result<I, ParseErrorKind> {
+    I::parse(buffer)
+}
 
-    if is_negative {
-        // Don't forget to put the minus sign there.
-        buffer[buffer_index] = b'-';
-        buffer_index += 1;
-    }
+/// Writes the value as hex digits to the buffer.
+///
+/// Signed values are written as their two's complement, so `-1i8` is `FF`.
+/// Returns the slice of the buffer that was written to.
+///
+/// Panics if the buffer is less than [MAX_HEX_DIGITS] long.
+pub fn write_hex<I: Integer>(buffer: &mut [u8], value: I, uppercase: bool) -> &mut [u8] {
+    // Check in debug mode if the buffer is long enough.
+    // We don't do this in release to have less overhead.
+    debug_assert!(buffer.len() >= MAX_HEX_DIGITS);
+
+    let buffer_index = value.write_hex(buffer, uppercase);
 
     // We built the buffer in reverse, so now we've got to undo that.
     buffer[0..buffer_index].reverse();
@@ -50,39 +284,74 @@ pub fn write_int(buffer: &mut [u8], mut value: i32) -> &mut [u8] {
     &mut buffer[0..buffer_index]
 }
 
-/// Parses an int
-pub fn parse_int(mut buffer: &[u8]) -> Option<i32> {
-    if buffer.is_empty() || buffer.len() > MAX_INT_DIGITS {
-        return None;
-    }
+/// Parses hex digits of any case.
+///
+/// Values with more bits than the type are an [ParseErrorKind::IntOverflow] error.
+pub fn parse_hex<I: Integer>(buffer: &[u8]) -> Result<I, ParseErrorKind> {
+    I::parse_hex(buffer)
+}
 
-    let is_negative = buffer[0] == b'-';
+/// Converts an int to a length.
+///
+/// Returns None if it's negative or too big.
+pub fn to_usize<I: Integer>(value: I) -> Option<usize> {
+    value.to_usize()
+}
+
+/// Gets the two hex digits of a byte
+pub fn hex_byte(byte: u8, uppercase: bool) -> [u8; 2] {
+    let digits = if uppercase {
+        HEX_DIGITS_UPPER
+    } else {
+        HEX_DIGITS_LOWER
+    };
+    [digits[(byte >> 4) as usize], digits[(byte & 0xF) as usize]]
+}
 
-    if is_negative {
-        buffer = &buffer[1..];
+/// Gets the value of a hex digit of any case
+pub fn hex_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
     }
+}
 
-    let mut value = 0;
-    for char in buffer.iter() {
-        if *char < b'0' || *char > b'9' {
-            return None;
-        } else {
-            value *= 10;
-            value -= (*char - b'0') as i32;
-        }
+/// Decodes pairs of hex digits into the buffer.
+///
+/// Returns the part of the buffer that was filled.
+pub fn decode_hex_bytes<'b>(hex: &[u8], buffer: &'b mut [u8]) -> Result<&'b [u8], ParseErrorKind> {
+    if hex.len() & 1 != 0 {
+        return Err(ParseErrorKind::InvalidHex);
     }
 
-    if is_negative {
-        Some(value)
-    } else {
-        Some(-value)
+    let length = hex.len() / 2;
+    let bytes = buffer
+        .get_mut(..length)
+        .ok_or(ParseErrorKind::BufferTooSmall)?;
+
+    for (byte, digits) in bytes.iter_mut().zip(hex.chunks_exact(2)) {
+        *byte = parse_hex::<u8>(digits).map_err(|_| ParseErrorKind::InvalidHex)?;
     }
+
+    Ok(bytes)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_try_append_data() {
+        let mut buffer = [0; 4];
+
+        assert_eq!(try_append_data(&mut buffer, 0, b"AT"), 2);
+        assert_eq!(try_append_data(&mut buffer, 2, b"+CSQ"), 6);
+        assert_eq!(&buffer, b"AT\0\0");
+        assert_eq!(try_append_data(&mut buffer, 6, b"?"), 7);
+    }
+
     #[test]
     fn test_write_int() {
         let mut buffer = [0; 128];
@@ -94,22 +363,119 @@ mod tests {
         assert_eq!(write_int(&mut buffer, 42), b"42");
         assert_eq!(write_int(&mut buffer, -2147483648), b"-2147483648");
         assert_eq!(write_int(&mut buffer, 2147483647), b"2147483647");
+        assert_eq!(write_int(&mut buffer, i8::MIN), b"-128");
+        assert_eq!(write_int(&mut buffer, u32::MAX), b"4294967295");
+        assert_eq!(write_int(&mut buffer, i64::MIN), b"-9223372036854775808");
+        assert_eq!(write_int(&mut buffer, u64::MAX), b"18446744073709551615");
+        assert_eq!(write_int(&mut buffer, 0u64), b"0");
     }
 
     #[test]
     fn test_parse_int() {
-        assert_eq!(parse_int(b"0"), Some(0));
-        assert_eq!(parse_int(b"-1"), Some(-1));
-        assert_eq!(parse_int(b"1"), Some(1));
-        assert_eq!(parse_int(b"-42"), Some(-42));
-        assert_eq!(parse_int(b"42"), Some(42));
-        assert_eq!(parse_int(b"-2147483648"), Some(-2147483648));
-        assert_eq!(parse_int(b"2147483647"), Some(2147483647));
-
-        assert_eq!(parse_int(b""), None);
-        assert_eq!(parse_int(b"abc"), None);
-        assert_eq!(parse_int(b"-b"), None);
-        assert_eq!(parse_int(b"123456a"), None);
-        assert_eq!(parse_int(b"z12354"), None);
+        assert_eq!(parse_int(b"0"), Ok(0));
+        assert_eq!(parse_int(b"-1"), Ok(-1));
+        assert_eq!(parse_int(b"1"), Ok(1));
+        assert_eq!(parse_int(b"-42"), Ok(-42));
+        assert_eq!(parse_int(b"42"), Ok(42));
+        assert_eq!(parse_int(b"-2147483648"), Ok(-2147483648));
+        assert_eq!(parse_int(b"2147483647"), Ok(2147483647));
+        assert_eq!(parse_int(b"-9223372036854775808"), Ok(i64::MIN));
+        assert_eq!(parse_int(b"18446744073709551615"), Ok(u64::MAX));
+        assert_eq!(parse_int(b"000000000000000000042"), Ok(42u8));
+
+        assert_eq!(parse_int::<i32>(b""), Err(ParseErrorKind::InvalidInt));
+        assert_eq!(parse_int::<i32>(b"-"), Err(ParseErrorKind::InvalidInt));
+        assert_eq!(parse_int::<i32>(b"abc"), Err(ParseErrorKind::InvalidInt));
+        assert_eq!(parse_int::<i32>(b"-b"), Err(ParseErrorKind::InvalidInt));
+        assert_eq!(
+            parse_int::<i32>(b"123456a"),
+            Err(ParseErrorKind::InvalidInt)
+        );
+        assert_eq!(parse_int::<i32>(b"z12354"), Err(ParseErrorKind::InvalidInt));
+        assert_eq!(parse_int::<u32>(b"-1"), Err(ParseErrorKind::InvalidInt));
+    }
+
+    #[test]
+    fn test_parse_int_overflow() {
+        assert_eq!(
+            parse_int::<i32>(b"2147483648"),
+            Err(ParseErrorKind::IntOverflow)
+        );
+        assert_eq!(
+            parse_int::<i32>(b"-2147483649"),
+            Err(ParseErrorKind::IntOverflow)
+        );
+        assert_eq!(
+            parse_int::<i32>(b"99999999999"),
+            Err(ParseErrorKind::IntOverflow)
+        );
+        assert_eq!(parse_int::<u8>(b"256"), Err(ParseErrorKind::IntOverflow));
+        assert_eq!(parse_int::<i8>(b"128"), Err(ParseErrorKind::IntOverflow));
+        assert_eq!(
+            parse_int::<u64>(b"18446744073709551616"),
+            Err(ParseErrorKind::IntOverflow)
+        );
+        assert_eq!(
+            parse_int::<i64>(b"9223372036854775808"),
+            Err(ParseErrorKind::IntOverflow)
+        );
+    }
+
+    #[test]
+    fn test_write_hex() {
+        let mut buffer = [0; 128];
+
+        assert_eq!(write_hex(&mut buffer, 0u8, true), b"0");
+        assert_eq!(write_hex(&mut buffer, 0x1A2Bu16, true), b"1A2B");
+        assert_eq!(write_hex(&mut buffer, 0x1A2Bu16, false), b"1a2b");
+        assert_eq!(write_hex(&mut buffer, 0x80084, true), b"80084");
+        assert_eq!(write_hex(&mut buffer, -1i8, true), b"FF");
+        assert_eq!(write_hex(&mut buffer, u64::MAX, false), b"ffffffffffffffff");
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex(b"0"), Ok(0u8));
+        assert_eq!(parse_hex(b"1A2B"), Ok(0x1A2Bu16));
+        assert_eq!(parse_hex(b"1a2b"), Ok(0x1A2Bu32));
+        assert_eq!(parse_hex(b"0000FF"), Ok(255u8));
+        assert_eq!(parse_hex(b"FF"), Ok(-1i8));
+        assert_eq!(parse_hex(b"ffffffffffffffff"), Ok(u64::MAX));
+
+        assert_eq!(parse_hex::<u32>(b""), Err(ParseErrorKind::InvalidInt));
+        assert_eq!(parse_hex::<u32>(b"1G"), Err(ParseErrorKind::InvalidInt));
+        assert_eq!(parse_hex::<u32>(b"-1"), Err(ParseErrorKind::InvalidInt));
+        assert_eq!(parse_hex::<u8>(b"100"), Err(ParseErrorKind::IntOverflow));
+        assert_eq!(parse_hex::<i8>(b"1FF"), Err(ParseErrorKind::IntOverflow));
+    }
+
+    #[test]
+    fn test_hex_bytes() {
+        let mut buffer = [0; 4];
+
+        assert_eq!(hex_byte(0xA5, true), *b"A5");
+        assert_eq!(hex_byte(0x0F, false), *b"0f");
+        assert_eq!(hex_value(b'7'), Some(7));
+        assert_eq!(hex_value(b'c'), Some(12));
+        assert_eq!(hex_value(b'C'), Some(12));
+        assert_eq!(hex_value(b'G'), None);
+
+        assert_eq!(decode_hex_bytes(b"", &mut buffer), Ok(&[][..]));
+        assert_eq!(
+            decode_hex_bytes(b"00A5ff", &mut buffer),
+            Ok(&[0x00, 0xA5, 0xFF][..])
+        );
+        assert_eq!(
+            decode_hex_bytes(b"A5F", &mut buffer),
+            Err(ParseErrorKind::InvalidHex)
+        );
+        assert_eq!(
+            decode_hex_bytes(b"+5", &mut buffer),
+            Err(ParseErrorKind::InvalidHex)
+        );
+        assert_eq!(
+            decode_hex_bytes(b"0011223344", &mut buffer),
+            Err(ParseErrorKind::BufferTooSmall)
+        );
     }
 }
@@ -1,9 +1,28 @@
 //! Crate for building and parsing AT Commands
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![deny(missing_docs)]
 
+// Lets the derive macros refer to this crate by name, also from within this crate
+extern crate self as at_commands;
+
 pub mod builder;
+#[cfg(any(feature = "embedded-io", feature = "embedded-io-async"))]
+pub mod client;
+pub mod command;
+pub mod dce;
+pub mod digester;
+pub mod escape;
 pub(crate) mod formatter;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod parser;
+pub mod range_list;
+pub mod response_builder;
+pub mod result_code;
+#[cfg(feature = "transcript")]
+pub mod transcript;
 pub(crate) mod tuple_concat;
+pub mod urc;
+
+pub use formatter::Integer;
@@ -0,0 +1,685 @@
+//! Module with a scripted fake modem for testing drivers on the host
+
+use core::cell::{Cell, RefCell};
+use core::task::Waker;
+
+/// A step in the script of a [MockModem]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Step<'a> {
+    /// The modem expects the host to write exactly these bytes, including the line terminator
+    Expect(&'a [u8]),
+    /// The modem sends these bytes as they are, like a canned response
+    Respond(&'a [u8]),
+    /// The modem sends an unsolicited result code.
+    ///
+    /// It is framed as `\r\n{urc}\r\n`, so it doesn't need line terminators.
+    Urc(&'a [u8]),
+    /// The modem waits this many milliseconds before it sends the next bytes
+    Delay(u32),
+}
+
+/// What went wrong while running the script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MockError {
+    /// The host wrote something else than the command of the [Step::Expect] with the given index.
+    ///
+    /// The offset is where in the expected command the first wrong byte is.
+    /// If the index is the length of the script, then the host wrote after all commands were done.
+    Unexpected {
+        /// The index of the step in the script
+        step: usize,
+        /// The index of the first wrong byte in the expected command
+        offset: usize,
+    },
+    /// The host tried a blocking read while the modem waits for the command of the step with the given index.
+    /// This read would never end.
+    Blocked {
+        /// The index of the step in the script
+        step: usize,
+    },
+    /// The script wasn't done. The step with the given index is the first that didn't happen.
+    Missing {
+        /// The index of the step in the script
+        step: usize,
+    },
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Error for MockError {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::Other
+    }
+}
+
+/// # MockModem
+/// A fake modem that follows a script
+///
+/// The script is a list of [Step]s: the commands the modem expects, the responses it sends,
+/// the URCs it injects and the delays in between.
+/// The modem sends everything up to the next [Step::Expect] and then waits for the host to write that command.
+///
+/// The host talks to the modem through a [MockIo], which implements the `embedded-io` traits
+/// and, with the `embedded-io-async` feature, the `embedded-io-async` traits.
+/// So the whole flow of a driver, including the [AtClient](crate::client::AtClient), can be tested.
+///
+/// The modem has its own clock that only runs when the host looks at it through a [MockClock]
+/// or waits for the modem. That way delays and timeouts are the same on every test run.
+///
+/// Writing something else than expected makes the write fail with a [MockError],
+/// and [MockModem::finish] reports the first error or the steps that never happened.
+///
+/// ## Example
+/// ```rust
+/// use at_commands::builder::CommandBuilder;
+/// use at_commands::client::AtClient;
+/// use at_commands::mock::{MockError, MockModem, Step};
+/// use at_commands::result_code::FinalResult;
+///
+/// let modem = MockModem::new(&[
+///     Step::Expect(b"AT+CSQ\r\n"),
+///     Step::Delay(100),
+///     Step::Respond(b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n"),
+///     Step::Expect(b"AT+CGMR\r\n"),
+///     Step::Respond(b"\r\n+CGMR: 1.0\r\n\r\nOK\r\n"),
+/// ]);
+///
+/// let mut client = AtClient::<_, _, 64>::new(modem.io(), modem.clock(), 1000);
+///
+/// let mut buffer = [0; 32];
+/// let command = CommandBuilder::create_execute(&mut buffer, true)
+///     .named("+CSQ")
+///     .finish()
+///     .unwrap();
+///
+/// let response = client.send(command).unwrap();
+/// assert_eq!(response.result(), Some(FinalResult::Ok));
+///
+/// // The `AT+CGMR` was never sent
+/// assert_eq!(modem.finish(), Err(MockError::Missing { step: 3 }));
+/// ```
+pub struct MockModem<'a> {
+    steps: &'a [Step<'a>],
+    state: RefCell<State>,
+    /// The waker of an async read that waits for the host to write
+    waker: Cell<Option<Waker>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// The step that is read from next
+    read_step: usize,
+    /// How much of the output of the read step has been read
+    read_offset: usize,
+    /// The time until which the delay of the read step lasts, once it has started
+    delay_until: Option<u64>,
+    /// The step that is written to next
+    write_step: usize,
+    /// How much of the command of the write step has been written
+    write_offset: usize,
+    now_ms: u64,
+    /// The first error, which all later operations give as well
+    error: Option<MockError>,
+}
+
+/// What the host can read from the modem
+enum Readable<'a> {
+    /// The next bytes of the output
+    Data(&'a [u8]),
+    /// The modem is in a delay that lasts until the given time
+    Delay(u64),
+    /// The modem waits for the command of the step with the given index
+    Expect(usize),
+    /// The script is done
+    End,
+}
+
+impl<'a> MockModem<'a> {
+    /// Creates a modem that follows the script
+    pub fn new(steps: &'a [Step<'a>]) -> Self {
+        Self {
+            steps,
+            state: RefCell::new(State::default()),
+            waker: Cell::new(None),
+        }
+    }
+
+    /// The interface to talk to the modem with.
+    ///
+    /// All interfaces of the modem are the same, so one can be used to read and another to write.
+    pub fn io(&self) -> MockIo<'_> {
+        MockIo { modem: self }
+    }
+
+    /// The clock of the modem, for the timeouts of the host
+    pub fn clock(&self) -> MockClock<'_> {
+        MockClock { modem: self }
+    }
+
+    /// The time of the clock of the modem, without moving it forward
+    pub fn now_ms(&self) -> u64 {
+        self.state.borrow().now_ms
+    }
+
+    /// Checks that the whole script happened without errors
+    pub fn finish(&self) -> Result<(), MockError> {
+        let state = self.state.borrow();
+
+        if let Some(error) = state.error {
+            return Err(error);
+        }
+
+        let remaining_reads = self.steps.get(state.read_step..).unwrap_or(&[]);
+        let remaining_writes = self.steps.get(state.write_step..).unwrap_or(&[]);
+
+        // Delays at the end can't be told apart from a modem that is done
+        let read_step = if remaining_reads
+            .iter()
+            .all(|step| matches!(step, Step::Delay(_)))
+        {
+            self.steps.len()
+        } else {
+            state.read_step
+        };
+        let write_step = state.write_step
+            + remaining_writes
+                .iter()
+                .position(|step| matches!(step, Step::Expect(_)))
+                .unwrap_or(remaining_writes.len());
+
+        let step = read_step.min(write_step);
+        if step < self.steps.len() {
+            return Err(MockError::Missing { step });
+        }
+
+        Ok(())
+    }
+
+    /// Finds what the host can read, moving past the steps that are done
+    fn readable(&self, state: &mut State) -> Readable<'a> {
+        loop {
+            let step = match self.steps.get(state.read_step) {
+                Some(step) => step,
+                None => return Readable::End,
+            };
+
+            match step {
+                Step::Expect(_) if state.write_step <= state.read_step => {
+                    return Readable::Expect(state.read_step)
+                }
+                Step::Delay(delay) => {
+                    let until = *state
+                        .delay_until
+                        .get_or_insert(state.now_ms + u64::from(*delay));
+                    if state.now_ms < until {
+                        return Readable::Delay(until);
+                    }
+                    state.delay_until = None;
+                }
+                Step::Respond(bytes) => {
+                    if let Some(data) = bytes.get(state.read_offset..).filter(|d| !d.is_empty()) {
+                        return Readable::Data(data);
+                    }
+                }
+                Step::Urc(urc) => {
+                    // The URC with its framing, as the parts `\r\n`, the URC and `\r\n`
+                    let offset = state.read_offset;
+                    let data = match offset.checked_sub(2) {
+                        None => Some(&b"\r\n"[offset..]),
+                        Some(offset) if offset < urc.len() => Some(&urc[offset..]),
+                        Some(offset) => b"\r\n".get(offset - urc.len()..),
+                    };
+                    if let Some(data) = data.filter(|data| !data.is_empty()) {
+                        return Readable::Data(data);
+                    }
+                }
+                Step::Expect(_) => {}
+            }
+
+            state.read_step += 1;
+            state.read_offset = 0;
+        }
+    }
+
+    /// Reads what is available without waiting
+    fn read_available(&self, state: &mut State, buffer: &mut [u8]) -> usize {
+        let mut length = 0;
+
+        while length < buffer.len() {
+            let Readable::Data(data) = self.readable(state) else {
+                break;
+            };
+            let taken = data.len().min(buffer.len() - length);
+            buffer[length..length + taken].copy_from_slice(&data[..taken]);
+            state.read_offset += taken;
+            length += taken;
+        }
+
+        length
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, MockError> {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+
+        if let Some(error) = state.error {
+            return Err(error);
+        }
+
+        let mut remaining = data;
+        while !remaining.is_empty() {
+            let command = match self.steps.get(state.write_step) {
+                Some(Step::Expect(command)) => command,
+                Some(_) => {
+                    state.write_step += 1;
+                    continue;
+                }
+                None => {
+                    return Err(*state.error.insert(MockError::Unexpected {
+                        step: state.write_step,
+                        offset: 0,
+                    }))
+                }
+            };
+
+            let expected = &command[state.write_offset..];
+            let length = expected.len().min(remaining.len());
+            if let Some(wrong) = (0..length).find(|index| expected[*index] != remaining[*index]) {
+                return Err(*state.error.insert(MockError::Unexpected {
+                    step: state.write_step,
+                    offset: state.write_offset + wrong,
+                }));
+            }
+
+            state.write_offset += length;
+            remaining = &remaining[length..];
+
+            if state.write_offset == command.len() {
+                state.write_step += 1;
+                state.write_offset = 0;
+            }
+        }
+
+        // A read may be waiting for this command
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+
+        Ok(data.len())
+    }
+}
+
+/// The interface of a [MockModem], which the host reads from and writes to
+pub struct MockIo<'m> {
+    modem: &'m MockModem<'m>,
+}
+
+/// The clock of a [MockModem].
+///
+/// Every time it is read, it moves a millisecond forward.
+/// With the `embedded-io-async` feature, it is also the delay of the
+/// [AtHandle](crate::client::asynch::AtHandle), which moves it forward every time it is polled.
+pub struct MockClock<'m> {
+    modem: &'m MockModem<'m>,
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl MockClock<'_> {
+    /// Waits until the clock has moved `ms` milliseconds forward
+    async fn wait_ms(&mut self, ms: u64) {
+        let until = self.modem.now_ms() + ms;
+
+        core::future::poll_fn(|context| {
+            let mut state = self.modem.state.borrow_mut();
+            if state.now_ms >= until {
+                return core::task::Poll::Ready(());
+            }
+
+            state.now_ms += 1;
+            context.waker().wake_by_ref();
+            core::task::Poll::Pending
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl crate::client::Clock for MockClock<'_> {
+    fn now_ms(&self) -> u64 {
+        let mut state = self.modem.state.borrow_mut();
+        state.now_ms += 1;
+        state.now_ms
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl embedded_hal_async::delay::DelayNs for MockClock<'_> {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.wait_ms(u64::from(ns.div_ceil(1_000_000))).await
+    }
+
+    async fn delay_ms(&mut self, ms: u32) {
+        self.wait_ms(u64::from(ms)).await
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ErrorType for MockIo<'_> {
+    type Error = MockError;
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Read for MockIo<'_> {
+    /// Reads what the modem sends.
+    ///
+    /// A delay is waited out right away by moving the clock forward.
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, MockError> {
+        let modem = self.modem;
+        let mut state = modem.state.borrow_mut();
+        let state = &mut *state;
+
+        loop {
+            if let Some(error) = state.error {
+                return Err(error);
+            }
+
+            match modem.readable(state) {
+                Readable::Data(_) => return Ok(modem.read_available(state, buffer)),
+                Readable::Delay(until) => state.now_ms = until,
+                Readable::Expect(step) => {
+                    return Err(*state.error.insert(MockError::Blocked { step }))
+                }
+                Readable::End => return Ok(0),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ReadReady for MockIo<'_> {
+    /// Returns true if there's something to read.
+    ///
+    /// If there's not, time passes while the host waits, so the clock moves a millisecond forward.
+    fn read_ready(&mut self) -> Result<bool, MockError> {
+        let mut state = self.modem.state.borrow_mut();
+        let state = &mut *state;
+
+        if let Some(error) = state.error {
+            return Err(error);
+        }
+
+        match self.modem.readable(state) {
+            Readable::Data(_) | Readable::End => Ok(true),
+            Readable::Delay(_) | Readable::Expect(_) => {
+                state.now_ms += 1;
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Write for MockIo<'_> {
+    fn write(&mut self, buffer: &[u8]) -> Result<usize, MockError> {
+        self.modem.write(buffer)
+    }
+
+    fn flush(&mut self) -> Result<(), MockError> {
+        self.modem.state.borrow().error.map_or(Ok(()), Err)
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl embedded_io_async::Read for MockIo<'_> {
+    /// Reads what the modem sends, waiting until the host wrote the command the modem expects.
+    ///
+    /// A delay is waited out right away by moving the clock forward.
+    async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, MockError> {
+        let modem = self.modem;
+
+        core::future::poll_fn(|context| {
+            let mut state = modem.state.borrow_mut();
+            let state = &mut *state;
+
+            loop {
+                if let Some(error) = state.error {
+                    return core::task::Poll::Ready(Err(error));
+                }
+
+                match modem.readable(state) {
+                    Readable::Data(_) => {
+                        return core::task::Poll::Ready(Ok(modem.read_available(state, buffer)))
+                    }
+                    Readable::Delay(until) => state.now_ms = until,
+                    Readable::Expect(_) => {
+                        modem.waker.set(Some(context.waker().clone()));
+                        return core::task::Poll::Pending;
+                    }
+                    Readable::End => return core::task::Poll::Ready(Ok(0)),
+                }
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl embedded_io_async::Write for MockIo<'_> {
+    async fn write(&mut self, buffer: &[u8]) -> Result<usize, MockError> {
+        self.modem.write(buffer)
+    }
+
+    async fn flush(&mut self) -> Result<(), MockError> {
+        self.modem.state.borrow().error.map_or(Ok(()), Err)
+    }
+}
+
+#[cfg(all(test, feature = "embedded-io"))]
+mod tests {
+    use super::*;
+    use crate::client::{AtClient, ClientError, Clock};
+    use crate::result_code::FinalResult;
+    use crate::urc::UrcDispatcher;
+    use embedded_io::{Read, ReadReady, Write};
+
+    #[test]
+    fn test_script() {
+        let modem = MockModem::new(&[
+            Step::Urc(b"+CPIN: READY"),
+            Step::Expect(b"ATE0\r\n"),
+            Step::Respond(b"ATE0\r\r\nOK\r\n"),
+            Step::Expect(b"AT+CREG?\r\n"),
+            Step::Delay(50),
+            Step::Urc(b"RING"),
+            Step::Respond(b"\r\n+CREG: 0,1\r\n\r\nOK\r\n"),
+        ]);
+
+        let mut rings = 0;
+        let mut on_ring = |_: &[u8]| rings += 1;
+
+        {
+            let mut urcs = UrcDispatcher::<1>::new();
+            urcs.register(b"RING", &mut on_ring).unwrap();
+
+            let mut client = AtClient::<_, _, 64>::new(modem.io(), modem.clock(), 100);
+
+            // The `+CPIN` from before the command is not part of the response
+            let response = client.send(b"ATE0\r\n").unwrap();
+            assert_eq!(response.raw(), b"\r\nOK\r\n");
+
+            let response = client.send_with_urcs(b"AT+CREG?\r\n", &mut urcs).unwrap();
+            assert_eq!(response.raw(), b"\r\n+CREG: 0,1\r\n\r\nOK\r\n");
+            assert_eq!(response.result(), Some(FinalResult::Ok));
+        }
+
+        assert_eq!(rings, 1);
+        assert!(modem.now_ms() >= 50);
+        assert_eq!(modem.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_unexpected_and_missing() {
+        let script = [
+            Step::Expect(b"AT+CSQ\r\n"),
+            Step::Respond(b"\r\nOK\r\n"),
+            Step::Expect(b"AT+CGMR\r\n"),
+            Step::Respond(b"\r\nOK\r\n"),
+        ];
+
+        let modem = MockModem::new(&script);
+        let mut client = AtClient::<_, _, 64>::new(modem.io(), modem.clock(), 100);
+        assert!(client.send(b"AT+CSQ\r\n").is_ok());
+        assert_eq!(modem.finish(), Err(MockError::Missing { step: 2 }));
+
+        let modem = MockModem::new(&script);
+        let mut client = AtClient::<_, _, 64>::new(modem.io(), modem.clock(), 100);
+        let error = MockError::Unexpected { step: 0, offset: 4 };
+        assert_eq!(client.send(b"AT+CGMR\r\n"), Err(ClientError::Io(error)));
+        assert_eq!(modem.finish(), Err(error));
+
+        // Writing after the script is done
+        let modem = MockModem::new(&script[..2]);
+        let mut io = modem.io();
+        io.write_all(b"AT+CSQ\r\n").unwrap();
+        assert_eq!(
+            io.write(b"AT"),
+            Err(MockError::Unexpected { step: 2, offset: 0 })
+        );
+    }
+
+    #[test]
+    fn test_timing() {
+        let modem = MockModem::new(&[
+            Step::Expect(b"AT+COPS=?\r\n"),
+            Step::Delay(500),
+            Step::Respond(b"\r\nOK\r\n"),
+        ]);
+
+        // The response comes after the timeout
+        let mut client = AtClient::<_, _, 64>::new(modem.io(), modem.clock(), 100);
+        assert_eq!(client.send(b"AT+COPS=?\r\n"), Err(ClientError::Timeout));
+        assert!(modem.now_ms() < 500);
+        assert_eq!(modem.finish(), Err(MockError::Missing { step: 1 }));
+
+        // A blocking read waits the delay out
+        let (mut io, clock) = client.release();
+        let mut buffer = [0; 16];
+        assert_eq!(io.read(&mut buffer), Ok(6));
+        assert_eq!(&buffer[..6], b"\r\nOK\r\n");
+        assert!(clock.now_ms() > 500);
+        assert_eq!(io.read_ready(), Ok(true));
+        assert_eq!(io.read(&mut buffer), Ok(0));
+        assert_eq!(modem.finish(), Ok(()));
+
+        // A blocking read that would never end
+        let modem = MockModem::new(&[Step::Expect(b"AT\r\n")]);
+        assert_eq!(
+            modem.io().read(&mut buffer),
+            Err(MockError::Blocked { step: 0 })
+        );
+    }
+
+    #[test]
+    fn test_late_result() {
+        let modem = MockModem::new(&[
+            Step::Expect(b"AT+COPS=?\r\n"),
+            Step::Delay(150),
+            Step::Respond(b"\r\n+COPS: (1,\"A\")\r\n\r\nOK\r\n"),
+            Step::Expect(b"AT+CSQ\r\n"),
+            Step::Respond(b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n"),
+            Step::Expect(b"AT+CGMR\r\n"),
+            Step::Expect(b"AT\r\n"),
+            Step::Respond(b"\r\nOK\r\n"),
+        ]);
+
+        let mut client = AtClient::<_, _, 64>::new(modem.io(), modem.clock(), 100);
+        assert_eq!(client.send(b"AT+COPS=?\r\n"), Err(ClientError::Timeout));
+
+        // The late response is waited for and dropped before the next command is sent
+        let response = client.send(b"AT+CSQ\r\n").unwrap();
+        assert_eq!(response.raw(), b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n");
+
+        // A response that never comes is waited for up to the timeout
+        assert_eq!(client.send(b"AT+CGMR\r\n"), Err(ClientError::Timeout));
+        let response = client.send(b"AT\r\n").unwrap();
+        assert_eq!(response.result(), Some(FinalResult::Ok));
+        assert_eq!(modem.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_leftover_bytes() {
+        let modem = MockModem::new(&[
+            Step::Expect(b"AT\r\n"),
+            Step::Respond(b"\r\nOK\r\n\r\nRING\r\n\r\n+CREG: 1\r\n"),
+            Step::Expect(b"AT+CREG?\r\n"),
+            Step::Respond(b"\r\n+CREG: 0,1\r\n\r\nOK\r\n"),
+        ]);
+
+        let mut rings = 0;
+        let mut on_ring = |_: &[u8]| rings += 1;
+        let mut registrations = 0;
+        let mut on_creg = |_: &[u8]| registrations += 1;
+
+        {
+            let mut urcs = UrcDispatcher::<2>::new();
+            urcs.register(b"RING", &mut on_ring).unwrap();
+            urcs.register(b"+CREG:", &mut on_creg).unwrap();
+
+            // The digester is too small to take everything that is read at once
+            let mut client = AtClient::<_, _, 20>::new(modem.io(), modem.clock(), 100);
+            let response = client.send_with_urcs(b"AT\r\n", &mut urcs).unwrap();
+            assert_eq!(response.result(), Some(FinalResult::Ok));
+
+            // The URCs that came after the response still go to their handlers,
+            // and the `+CREG:` of the response is not a URC
+            let response = client.send_with_urcs(b"AT+CREG?\r\n", &mut urcs).unwrap();
+            assert_eq!(response.raw(), b"\r\n+CREG: 0,1\r\n\r\nOK\r\n");
+        }
+
+        assert_eq!((rings, registrations), (1, 1));
+        assert_eq!(modem.finish(), Ok(()));
+    }
+
+    #[cfg(feature = "embedded-io-async")]
+    #[test]
+    fn test_async() {
+        use crate::client::asynch::AtChannel;
+        use embassy_futures::block_on;
+        use embassy_futures::join::join;
+        use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+
+        let modem = MockModem::new(&[
+            Step::Expect(b"AT+CSQ\r\n"),
+            Step::Delay(10),
+            Step::Respond(b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n"),
+            // Never answered
+            Step::Expect(b"AT+CGMR\r\n"),
+            Step::Expect(b"AT\r\n"),
+            Step::Respond(b"\r\nOK\r\n"),
+        ]);
+
+        let channel = AtChannel::<NoopRawMutex, 64>::new();
+        let (mut reader, mut handle) = channel.split(modem.io(), modem.io(), modem.clock(), 100);
+
+        let modem = &modem;
+        let client = async move {
+            let response = handle.send(b"AT+CSQ\r\n").await.unwrap();
+            assert_eq!(response.raw(), b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n");
+
+            let start = modem.now_ms();
+            assert_eq!(handle.send(b"AT+CGMR\r\n").await, Err(ClientError::Timeout));
+            assert_eq!(modem.now_ms() - start, 100);
+
+            // The late response is waited for up to the timeout, and then given up on
+            let response = handle.send(b"AT\r\n").await.unwrap();
+            assert_eq!(response.result(), Some(FinalResult::Ok));
+            assert!(modem.now_ms() - start >= 200);
+        };
+
+        let mut urcs = UrcDispatcher::<0>::new();
+        let (reader_result, _) = block_on(join(reader.run(&mut urcs), client));
+        assert_eq!(reader_result, Ok(()));
+        assert_eq!(modem.finish(), Ok(()));
+    }
+}
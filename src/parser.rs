@@ -1,6 +1,10 @@
 //! Module that defines the at command parser
 
-use crate::tuple_concat::TupleConcat;
+use crate::escape::Escaping;
+use crate::range_list::RangeList;
+use crate::result_code::{is_line_terminator, FinalResult};
+use crate::tuple_concat::{TupleConcat, TupleLast};
+use crate::Integer;
 
 /// ```
 /// use at_commands::parser::CommandParser;
@@ -30,7 +34,7 @@ use crate::tuple_concat::TupleConcat;
 pub struct CommandParser<'a, D> {
     buffer: &'a [u8],
     buffer_index: usize,
-    data_valid: bool,
+    data_valid: Result<(), ParseError<'a>>,
     data: D,
 }
 
@@ -40,29 +44,22 @@ impl<'a> CommandParser<'a, ()> {
         CommandParser {
             buffer,
             buffer_index: 0,
-            data_valid: true,
+            data_valid: Ok(()),
             data: (),
         }
     }
 }
 impl<'a, D> CommandParser<'a, D> {
     /// Tries reading an identifier
-    pub fn expect_identifier(mut self, identifier: &[u8]) -> Self {
+    pub fn expect_identifier(mut self, identifier: &'a [u8]) -> Self {
         // If we're already not valid, then quit
-        if !self.data_valid {
+        if self.data_valid.is_err() {
             return self;
         }
 
-        if self.buffer[self.buffer_index..].len() < identifier.len() {
-            self.data_valid = false;
-            return self;
+        if let Err(kind) = self.compare_identifier(identifier) {
+            return self.fail(kind, Expected::Identifier(identifier));
         }
-
-        // Zip together the identifier and the buffer data. If all bytes are the same, the data is valid.
-        self.data_valid = self.buffer[self.buffer_index..]
-            .iter()
-            .zip(identifier)
-            .all(|(buffer, id)| *buffer == *id);
         // Advance the index
         self.buffer_index += identifier.len();
 
@@ -70,9 +67,9 @@ impl<'a, D> CommandParser<'a, D> {
     }
 
     /// Tries reading an optional identifier.
-    pub fn expect_optional_identifier(mut self, identifier: &[u8]) -> Self {
+    pub fn expect_optional_identifier(mut self, identifier: &'a [u8]) -> Self {
         // If we're already not valid, then quit
-        if !self.data_valid {
+        if self.data_valid.is_err() {
             return self;
         }
 
@@ -81,26 +78,132 @@ impl<'a, D> CommandParser<'a, D> {
             return self;
         }
 
-        if self.buffer[self.buffer_index..].len() < identifier.len() {
-            self.data_valid = false;
+        if let Err(kind) = self.compare_identifier(identifier) {
+            return self.fail(kind, Expected::Identifier(identifier));
+        }
+        // Advance the index
+        self.buffer_index += identifier.len();
+
+        self.trim_space()
+    }
+
+    /// Tries reading one or more line breaks.
+    ///
+    /// This can be used to go to the next line of a response with multiple lines,
+    /// like the reply to commands that were chained on one line.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    ///
+    /// // The reply to `AT+CMEE=2;+CREG?;+CSQ`
+    /// let (n, stat, rssi, ber, _) =
+    ///     CommandParser::parse(b"\r\n+CREG: 2,1\r\n\r\n+CSQ: 20,99\r\n\r\nOK\r\n")
+    ///         .expect_line_break()
+    ///         .expect_identifier(b"+CREG:")
+    ///         .expect_int_parameter()
+    ///         .expect_int_parameter()
+    ///         .expect_line_break()
+    ///         .expect_identifier(b"+CSQ:")
+    ///         .expect_int_parameter()
+    ///         .expect_int_parameter()
+    ///         .expect_final_result()
+    ///         .finish()
+    ///         .unwrap();
+    ///
+    /// assert_eq!((n, stat), (2, 1));
+    /// assert_eq!((rssi, ber), (20, 99));
+    /// ```
+    pub fn expect_line_break(mut self) -> Self {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
             return self;
         }
 
-        // Zip together the identifier and the buffer data. If all bytes are the same, the data is valid.
-        self.data_valid = self.buffer[self.buffer_index..]
+        let line_breaks = self.buffer[self.buffer_index..]
             .iter()
-            .zip(identifier)
-            .all(|(buffer, id)| *buffer == *id);
+            .take_while(|byte| is_line_terminator(**byte))
+            .count();
+
+        if line_breaks == 0 {
+            let kind = self.missing_parameter_kind(ParseErrorKind::IdentifierMismatch);
+            return self.fail(kind, Expected::LineBreak);
+        }
         // Advance the index
-        self.buffer_index += identifier.len();
+        self.buffer_index += line_breaks;
 
         self.trim_space()
     }
 
+    /// Tries reading the `>` prompt with which a device asks for a payload.
+    ///
+    /// The line terminators before the prompt and the space after it are skipped.
+    /// After the prompt, the payload can be sent.
+    /// It can be built with [CommandBuilder::create_payload](crate::builder::CommandBuilder::create_payload).
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    ///
+    /// CommandParser::parse(b"AT+CMGS=\"+31612345678\"\r\r\n> ")
+    ///     .expect_identifier(b"AT+CMGS=\"+31612345678\"")
+    ///     .expect_prompt()
+    ///     .finish()
+    ///     .unwrap();
+    /// ```
+    pub fn expect_prompt(mut self) -> Self {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self;
+        }
+
+        let prompt_index = self.buffer_index
+            + self.buffer[self.buffer_index..]
+                .iter()
+                .take_while(|byte| is_line_terminator(**byte))
+                .count();
+
+        match self.buffer.get(prompt_index) {
+            Some(b'>') => {
+                self.buffer_index = prompt_index + 1;
+                self.trim_space()
+            }
+            Some(_) => self.fail(ParseErrorKind::IdentifierMismatch, Expected::Prompt),
+            None => self.fail(ParseErrorKind::UnexpectedEnd, Expected::Prompt),
+        }
+    }
+
+    /// Checks if the buffer continues with the given identifier
+    fn compare_identifier(&self, identifier: &[u8]) -> Result<(), ParseErrorKind> {
+        let remaining = &self.buffer[self.buffer_index..];
+
+        // Zip together the identifier and the buffer data. If all bytes are the same, the data is valid.
+        if !remaining
+            .iter()
+            .zip(identifier)
+            .all(|(buffer, id)| *buffer == *id)
+        {
+            Err(ParseErrorKind::IdentifierMismatch)
+        } else if remaining.len() < identifier.len() {
+            // What we have matches, but the identifier got cut off
+            Err(ParseErrorKind::UnexpectedEnd)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Marks the parser as invalid with an error that points at the current index
+    fn fail(mut self, kind: ParseErrorKind, expected: Expected<'a>) -> Self {
+        self.data_valid = Err(ParseError {
+            kind,
+            expected,
+            index: self.buffer_index,
+        });
+        self
+    }
+
     /// Moves the internal buffer index over the next bit of space characters, if any
     fn trim_space(mut self) -> Self {
         // If we're already not valid, then quit
-        if !self.data_valid {
+        if self.data_valid.is_err() {
             return self;
         }
 
@@ -132,9 +235,27 @@ impl<'a, D> CommandParser<'a, D> {
                 .unwrap_or(self.buffer.len())
     }
 
+    /// Finds the index of the character after the hex parameter or the end of the data.
+    fn find_end_of_hex_parameter(&self) -> usize {
+        self.buffer_index
+            + self
+                .buffer
+                .get(self.buffer_index..)
+                .map(|buffer| {
+                    buffer
+                        .iter()
+                        .take_while(|byte| byte.is_ascii_hexdigit())
+                        .count()
+                })
+                .unwrap_or(self.buffer.len())
+    }
+
     /// Finds the index of the character after the string parameter or the end of the data.
-    fn find_end_of_string_parameter(&self) -> usize {
+    ///
+    /// Quotes that are escaped don't end the string.
+    fn find_end_of_string_parameter(&self, escaping: Escaping) -> usize {
         let mut counted_quotes = 0;
+        let mut escaped = false;
 
         self.buffer_index
             + self
@@ -144,6 +265,11 @@ impl<'a, D> CommandParser<'a, D> {
                     buffer
                         .iter()
                         .take_while(|byte| {
+                            if escaped {
+                                escaped = false;
+                                return true;
+                            }
+                            escaped = escaping.is_escape(**byte);
                             counted_quotes += (**byte == b'"') as u8;
                             counted_quotes < 2
                         })
@@ -169,24 +295,47 @@ impl<'a, D> CommandParser<'a, D> {
                 .unwrap_or(self.buffer.len())
     }
 
-    fn parse_int_parameter(&self) -> (usize, bool, Option<i32>) {
-        let mut new_buffer_index = self.buffer_index;
+    /// Returns the kind of error to report for a required parameter that is missing.
+    ///
+    /// If there's no data left, that's the reason, otherwise it's the given kind.
+    fn missing_parameter_kind(&self, kind: ParseErrorKind) -> ParseErrorKind {
+        if self.buffer_index >= self.buffer.len() {
+            ParseErrorKind::UnexpectedEnd
+        } else {
+            kind
+        }
+    }
+
+    /// Like [Self::missing_parameter_kind], but for a quoted parameter.
+    ///
+    /// An empty `""` gets the same kind as an empty non-quoted parameter.
+    fn missing_quoted_parameter_kind(&self, empty_kind: ParseErrorKind) -> ParseErrorKind {
+        if self.buffer.get(self.buffer_index..self.buffer_index + 2) == Some(b"\"\"") {
+            empty_kind
+        } else {
+            self.missing_parameter_kind(ParseErrorKind::UnterminatedString)
+        }
+    }
+
+    fn parse_int_parameter<I: Integer>(&self) -> (usize, Result<Option<I>, ParseErrorKind>) {
         // Get the end index of the current parameter.
         let parameter_end = self.find_end_of_int_parameter();
         // Get the bytes in which the int should reside.
         let int_slice = match self.buffer.get(self.buffer_index..parameter_end) {
             None => {
-                return (new_buffer_index, false, None);
+                return (self.buffer_index, Err(ParseErrorKind::UnexpectedEnd));
             }
             Some(int_slice) => int_slice,
         };
+
+        // Advance the index to the character after the parameter separator (comma) if it's there.
+        let new_buffer_index =
+            parameter_end + (self.buffer.get(parameter_end) == Some(&b',')) as usize;
+
         if int_slice.is_empty() {
             // We probably hit the end of the buffer.
             // The parameter is empty but as it is optional not invalid
-            // Advance the index to the character after the parameter separator (comma) if it's there.
-            new_buffer_index =
-                parameter_end + (self.buffer.get(parameter_end) == Some(&b',')) as usize;
-            return (new_buffer_index, true, None);
+            return (new_buffer_index, Ok(None));
         }
 
         // Skip the leading '+'
@@ -197,29 +346,127 @@ impl<'a, D> CommandParser<'a, D> {
         };
 
         // Parse the int
-        let parsed_int = crate::formatter::parse_int(int_slice);
+        (
+            new_buffer_index,
+            crate::formatter::parse_int(int_slice).map(Some),
+        )
+    }
+
+    fn parse_hex_parameter<I: Integer>(&self) -> (usize, Result<Option<I>, ParseErrorKind>) {
+        let (new_buffer_index, result) = self.parse_hex_slice();
+        let result =
+            result.and_then(|hex_slice| hex_slice.map(crate::formatter::parse_hex).transpose());
+        (new_buffer_index, result)
+    }
+
+    /// Finds the hex digits of a non-quoted hex parameter
+    fn parse_hex_slice(&self) -> (usize, Result<Option<&'a [u8]>, ParseErrorKind>) {
+        // Get the end index of the current parameter.
+        let parameter_end = self.find_end_of_hex_parameter();
+        // Get the bytes in which the digits should reside.
+        let hex_slice = match self.buffer.get(self.buffer_index..parameter_end) {
+            None => {
+                return (self.buffer_index, Err(ParseErrorKind::UnexpectedEnd));
+            }
+            Some(hex_slice) => hex_slice,
+        };
 
         // Advance the index to the character after the parameter separator (comma) if it's there.
-        new_buffer_index = parameter_end + (self.buffer.get(parameter_end) == Some(&b',')) as usize;
-        // If we've found an int, then the data may be valid and we allow the closure to set the result ok data.
-        if let Some(parameter_value) = parsed_int {
-            (new_buffer_index, true, Some(parameter_value))
-        } else {
-            (new_buffer_index, false, None)
+        let new_buffer_index =
+            parameter_end + (self.buffer.get(parameter_end) == Some(&b',')) as usize;
+
+        if hex_slice.is_empty() {
+            // The parameter is empty but as it is optional not invalid
+            return (new_buffer_index, Ok(None));
         }
+
+        (new_buffer_index, Ok(Some(hex_slice)))
+    }
+
+    fn parse_hex_bytes_parameter<'b>(
+        &self,
+        quoted: bool,
+        bytes_buffer: &'b mut [u8],
+    ) -> (usize, Result<Option<&'b [u8]>, ParseErrorKind>) {
+        let (new_buffer_index, result) = if quoted {
+            self.parse_quoted_hex_slice()
+        } else {
+            self.parse_hex_slice()
+        };
+        let result = result.and_then(|hex_slice| {
+            hex_slice
+                .map(|hex_slice| crate::formatter::decode_hex_bytes(hex_slice, bytes_buffer))
+                .transpose()
+        });
+        (new_buffer_index, result)
+    }
+
+    fn parse_quoted_hex_parameter<I: Integer>(&self) -> (usize, Result<Option<I>, ParseErrorKind>) {
+        let (new_buffer_index, result) = self.parse_quoted_hex_slice();
+        let result =
+            result.and_then(|hex_slice| hex_slice.map(crate::formatter::parse_hex).transpose());
+        (new_buffer_index, result)
+    }
+
+    /// Finds the hex digits between the quotes of a quoted hex parameter
+    ///
+    /// An empty `""` has no digits, just like an empty non-quoted parameter.
+    fn parse_quoted_hex_slice(&self) -> (usize, Result<Option<&'a [u8]>, ParseErrorKind>) {
+        let (new_buffer_index, result) = self.parse_string_slice(Escaping::None);
+        let result = result.map(|hex_slice| hex_slice.filter(|hex_slice| !hex_slice.is_empty()));
+        (new_buffer_index, result)
+    }
+
+    fn parse_string_parameter(&self) -> (usize, Result<Option<&'a str>, ParseErrorKind>) {
+        let (new_buffer_index, result) = self.parse_string_slice(Escaping::None);
+        // If we've found a valid string, then the data may be valid and we allow the closure to set the result ok data.
+        let result = result.and_then(|string_slice| {
+            string_slice
+                .map(|string_slice| {
+                    core::str::from_utf8(string_slice).map_err(|_| ParseErrorKind::InvalidUtf8)
+                })
+                .transpose()
+        });
+        (new_buffer_index, result)
+    }
+
+    fn parse_escaped_string_parameter<'b>(
+        &self,
+        escaping: Escaping,
+        string_buffer: &'b mut [u8],
+    ) -> (usize, Result<Option<&'b str>, ParseErrorKind>) {
+        let (new_buffer_index, result) = self.parse_string_slice(escaping);
+        let result = result.and_then(|string_slice| {
+            string_slice
+                .map(|string_slice| {
+                    let string = escaping.unescape(string_slice, string_buffer)?;
+                    core::str::from_utf8(string).map_err(|_| ParseErrorKind::InvalidUtf8)
+                })
+                .transpose()
+        });
+        (new_buffer_index, result)
     }
 
-    fn parse_string_parameter(&self) -> (usize, bool, Option<&'a str>) {
-        let mut new_buffer_index = self.buffer_index;
+    /// Finds the bytes between the quotes of a string parameter
+    fn parse_string_slice(
+        &self,
+        escaping: Escaping,
+    ) -> (usize, Result<Option<&'a [u8]>, ParseErrorKind>) {
+        if self.buffer.get(self.buffer_index) == Some(&b',') {
+            // The parameter is empty but as it is optional not invalid
+            // Advance the index to the character after the parameter separator.
+            return (self.buffer_index + 1, Ok(None));
+        }
+
         // Get the end index of the current parameter.
-        let parameter_end = self.find_end_of_string_parameter();
+        let parameter_end = self.find_end_of_string_parameter(escaping);
         if parameter_end > self.buffer.len() {
             // We hit the end of the buffer.
             // The parameter is empty but as it is optional not invalid
-            return (new_buffer_index, true, None);
+            return (self.buffer_index, Ok(None));
         }
         // Get the bytes in which the string should reside.
-        let string_slice = &self.buffer[(new_buffer_index + 1)..(parameter_end - 1)];
+        let string_slice = &self.buffer[(self.buffer_index + 1)..(parameter_end - 1)];
 
         let has_comma_after_parameter = if let Some(next_char) = self.buffer.get(parameter_end) {
             *next_char == b','
@@ -228,223 +475,1127 @@ impl<'a, D> CommandParser<'a, D> {
         };
 
         // Advance the index to the character after the parameter separator.
-        new_buffer_index = parameter_end + has_comma_after_parameter as usize;
-        // If we've found a valid string, then the data may be valid and we allow the closure to set the result ok data.
-        if let Ok(parameter_value) = core::str::from_utf8(string_slice) {
-            (new_buffer_index, true, Some(parameter_value))
-        } else {
-            (new_buffer_index, false, None)
-        }
+        let new_buffer_index = parameter_end + has_comma_after_parameter as usize;
+        (new_buffer_index, Ok(Some(string_slice)))
     }
 
-    fn parse_raw_string_parameter(&self) -> (usize, bool, Option<&'a str>) {
-        let mut new_buffer_index = self.buffer_index;
+    fn parse_raw_string_parameter(&self) -> (usize, Result<Option<&'a str>, ParseErrorKind>) {
         // Get the end index of the current string.
         let end = self.find_end_of_raw_string();
         // Get the bytes in which the string should reside.
-        let string_slice = &self.buffer[new_buffer_index..(end - 1)];
+        let string_slice = &self.buffer[self.buffer_index..(end - 1)];
 
         // Advance the index to the character after the string.
-        new_buffer_index = end - 1usize;
+        let new_buffer_index = end - 1usize;
 
         // If we've found a valid string, then the data may be valid and we allow the closure to set the result ok data.
-        if let Ok(parameter_value) = core::str::from_utf8(string_slice) {
-            (new_buffer_index, true, Some(parameter_value))
-        } else {
-            (new_buffer_index, false, None)
+        match core::str::from_utf8(string_slice) {
+            Ok(parameter_value) => (new_buffer_index, Ok(Some(parameter_value))),
+            Err(_) => (new_buffer_index, Err(ParseErrorKind::InvalidUtf8)),
         }
     }
 
-    /// Finish parsing the command and get the results
-    pub fn finish(self) -> Result<D, ParseError> {
-        if self.data_valid {
-            Ok(self.data)
-        } else {
-            Err(ParseError(self.buffer_index))
+    fn parse_data(
+        &self,
+        separator: &[u8],
+        length: usize,
+    ) -> (usize, Result<&'a [u8], ParseErrorKind>) {
+        let data_start = self.buffer_index + separator.len();
+        // A huge length can't fit in the buffer anyway
+        let data = data_start
+            .checked_add(length)
+            .and_then(|data_end| Some((data_end, self.buffer.get(data_start..data_end)?)));
+
+        match data {
+            Some((data_end, data)) => (data_end, Ok(data)),
+            None => (self.buffer_index, Err(ParseErrorKind::UnexpectedEnd)),
         }
     }
-}
 
-impl<'a, D: TupleConcat<i32>> CommandParser<'a, D> {
-    /// Tries reading an int parameter
-    pub fn expect_int_parameter(self) -> CommandParser<'a, D::Out> {
-        // If we're already not valid, then quit
-        if !self.data_valid {
-            return CommandParser {
-                buffer: self.buffer,
-                buffer_index: self.buffer_index,
-                data_valid: self.data_valid,
-                data: self.data.tup_cat(0),
-            };
+    fn parse_range_list_parameter(&self) -> (usize, Result<Option<RangeList<'a>>, ParseErrorKind>) {
+        let remaining = self.buffer.get(self.buffer_index..).unwrap_or(&[]);
+
+        let items = match remaining {
+            [b'(', items @ ..] => items,
+            // An empty parameter
+            [] | [b',', ..] => {
+                let new_buffer_index = self.buffer_index + remaining.len().min(1);
+                return (new_buffer_index, Ok(None));
+            }
+            [byte, ..] if is_line_terminator(*byte) => return (self.buffer_index, Ok(None)),
+            _ => return (self.buffer_index, Err(ParseErrorKind::InvalidRangeList)),
+        };
+
+        let items_length = match items.iter().position(|byte| *byte == b')') {
+            Some(items_length) => items_length,
+            None => return (self.buffer_index, Err(ParseErrorKind::UnexpectedEnd)),
+        };
+
+        // The parentheses and the parameter separator (comma) if it's there
+        let parameter_end = self.buffer_index + items_length + 2;
+        let new_buffer_index =
+            parameter_end + (self.buffer.get(parameter_end) == Some(&b',')) as usize;
+
+        match RangeList::new(&items[..items_length]) {
+            Some(list) => (new_buffer_index, Ok(Some(list))),
+            None => (self.buffer_index, Err(ParseErrorKind::InvalidRangeList)),
         }
+    }
 
-        let (buffer_index, data_valid, data) = self.parse_int_parameter();
-        if let Some(parameter_value) = data {
-            return CommandParser {
+    fn parse_final_result(&self) -> (usize, Result<FinalResult<'a>, ParseErrorKind>) {
+        let count_line_terminators = |index: usize| {
+            self.buffer[index..]
+                .iter()
+                .take_while(|byte| is_line_terminator(**byte))
+                .count()
+        };
+
+        // Skip the line terminators before the result code
+        let line_start = self.buffer_index + count_line_terminators(self.buffer_index);
+        let line_end = line_start
+            + self.buffer[line_start..]
+                .iter()
+                .take_while(|byte| !is_line_terminator(**byte))
+                .count();
+
+        if line_start == line_end {
+            return (self.buffer_index, Err(ParseErrorKind::UnexpectedEnd));
+        }
+
+        match FinalResult::parse_line(&self.buffer[line_start..line_end]) {
+            // Advance the index over the line terminators after the result code
+            Some(result) => (line_end + count_line_terminators(line_end), Ok(result)),
+            None => (self.buffer_index, Err(ParseErrorKind::UnknownFinalResult)),
+        }
+    }
+
+    /// Adds the outcome of a parameter parse step to the data.
+    ///
+    /// When the step failed, the default value is added instead and the parser is marked invalid.
+    fn push_parameter<T>(
+        self,
+        expected: Expected<'a>,
+        default: T,
+        (buffer_index, result): (usize, Result<T, ParseErrorKind>),
+    ) -> CommandParser<'a, D::Out>
+    where
+        D: TupleConcat<T>,
+    {
+        match result {
+            Ok(parameter_value) => CommandParser {
                 buffer: self.buffer,
                 buffer_index,
-                data_valid,
+                data_valid: self.data_valid,
                 data: self.data.tup_cat(parameter_value),
             }
-            .trim_space();
-        } else {
-            return CommandParser {
+            .trim_space(),
+            Err(kind) => CommandParser {
                 buffer: self.buffer,
-                buffer_index,
-                data_valid: false,
-                data: self.data.tup_cat(0),
+                buffer_index: self.buffer_index,
+                data_valid: self.data_valid,
+                data: self.data.tup_cat(default),
             }
-            .trim_space();
+            .fail(kind, expected),
         }
     }
-}
 
-impl<'a, D: TupleConcat<&'a str>> CommandParser<'a, D> {
-    /// Tries reading a string parameter
-    pub fn expect_string_parameter(self) -> CommandParser<'a, D::Out> {
-        // If we're already not valid, then quit
-        if !self.data_valid {
-            return CommandParser {
+    /// Adds a default value to the data of a parser that is already invalid
+    fn skip_parameter<T>(self, default: T) -> CommandParser<'a, D::Out>
+    where
+        D: TupleConcat<T>,
+    {
+        CommandParser {
+            buffer: self.buffer,
+            buffer_index: self.buffer_index,
+            data_valid: self.data_valid,
+            data: self.data.tup_cat(default),
+        }
+    }
+
+    /// Takes out the data that has been parsed so far and gives back a parser without data
+    /// that continues where this one left off.
+    ///
+    /// This can be used to parse responses with more parameters than the data tuple can hold.
+    /// If the parser wasn't valid, the returned parser isn't either and the data is meaningless.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    ///
+    /// let ((x,), parser) = CommandParser::parse(b"+SYSGPIOREAD:654,\"true\"\r\nOK\r\n")
+    ///     .expect_identifier(b"+SYSGPIOREAD:")
+    ///     .expect_int_parameter()
+    ///     .take_data();
+    /// let (y,) = parser
+    ///     .expect_string_parameter()
+    ///     .expect_identifier(b"\r\nOK\r\n")
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(x, 654);
+    /// assert_eq!(y, "true");
+    /// ```
+    pub fn take_data(self) -> (D, CommandParser<'a, ()>) {
+        (
+            self.data,
+            CommandParser {
                 buffer: self.buffer,
                 buffer_index: self.buffer_index,
                 data_valid: self.data_valid,
-                data: self.data.tup_cat(""),
-            };
+                data: (),
+            },
+        )
+    }
+
+    /// Finish parsing the command and get the results
+    pub fn finish(self) -> Result<D, ParseError<'a>> {
+        self.data_valid.map(|()| self.data)
+    }
+
+    /// Parses the lines that start with the prefix, for responses that list the same thing multiple times.
+    ///
+    /// The returned iterator gives the result of the `parse` closure for every line, one at a time.
+    /// The closure gets a parser that starts after the prefix and ends at the end of the line.
+    /// Anything it doesn't parse at the end of the line is ignored.
+    /// The line terminators before every line are skipped, and the iteration stops at the first line
+    /// that doesn't start with the prefix.
+    ///
+    /// After a failing line, the iteration stops.
+    /// [Repeated::into_parser] gives back a parser that continues after the lines.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    /// use at_commands::result_code::FinalResult;
+    ///
+    /// let mut contexts = CommandParser::parse(
+    ///     b"\r\n+CGDCONT: 1,\"IP\",\"internet\"\r\n+CGDCONT: 2,\"IPV6\",\"ims\"\r\n\r\nOK\r\n",
+    /// )
+    /// .repeat_lines(b"+CGDCONT:", |parser| {
+    ///     parser
+    ///         .expect_int_parameter()
+    ///         .expect_string_parameter()
+    ///         .expect_string_parameter()
+    /// });
+    ///
+    /// assert_eq!(contexts.next(), Some(Ok((1, "IP", "internet"))));
+    /// assert_eq!(contexts.next(), Some(Ok((2, "IPV6", "ims"))));
+    /// assert_eq!(contexts.next(), None);
+    ///
+    /// let (result,) = contexts
+    ///     .into_parser()
+    ///     .expect_final_result()
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!(result, FinalResult::Ok);
+    /// ```
+    pub fn repeat_lines<T, F>(self, prefix: &'a [u8], parse: F) -> Repeated<'a, D, F>
+    where
+        F: FnMut(CommandParser<'a, ()>) -> CommandParser<'a, T>,
+    {
+        Repeated {
+            parser: self,
+            repetition: Repetition::Lines(prefix),
+            parse,
+            done: false,
         }
+    }
 
-        let (buffer_index, data_valid, data) = self.parse_string_parameter();
-        if let Some(parameter_value) = data {
-            return CommandParser {
-                buffer: self.buffer,
-                buffer_index,
-                data_valid,
-                data: self.data.tup_cat(parameter_value),
+    /// Parses the entries that start with a line with the prefix and go on with the lines after it,
+    /// like the messages of `+CMGL` that have their text on the line after the header.
+    ///
+    /// The returned iterator gives the result of the `parse` closure for every entry, one at a time.
+    /// The closure gets a parser that starts after the prefix and ends at the end of the entry,
+    /// so it can go to the lines of the body with [Self::expect_line_break].
+    /// An entry ends before the next line that starts with the prefix or is a final result code.
+    /// Anything the closure doesn't parse at the end of the entry is ignored.
+    ///
+    /// After a failing entry, the iteration stops.
+    /// [Repeated::into_parser] gives back a parser that continues after the entries.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    /// use at_commands::result_code::FinalResult;
+    ///
+    /// let mut messages = CommandParser::parse(
+    ///     b"\r\n+CMGL: 1,\"REC READ\"\r\nhello\r\n+CMGL: 2,\"REC UNREAD\"\r\nworld\r\n\r\nOK\r\n",
+    /// )
+    /// .repeat_entries(b"+CMGL:", |parser| {
+    ///     parser
+    ///         .expect_int_parameter()
+    ///         .expect_string_parameter()
+    ///         .expect_line_break()
+    ///         .expect_raw_string()
+    /// });
+    ///
+    /// assert_eq!(messages.next(), Some(Ok((1, "REC READ", "hello"))));
+    /// assert_eq!(messages.next(), Some(Ok((2, "REC UNREAD", "world"))));
+    /// assert_eq!(messages.next(), None);
+    ///
+    /// let (result,) = messages
+    ///     .into_parser()
+    ///     .expect_final_result()
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!(result, FinalResult::Ok);
+    /// ```
+    pub fn repeat_entries<T, F>(self, prefix: &'a [u8], parse: F) -> Repeated<'a, D, F>
+    where
+        F: FnMut(CommandParser<'a, ()>) -> CommandParser<'a, T>,
+    {
+        Repeated {
+            parser: self,
+            repetition: Repetition::Entries(prefix),
+            parse,
+            done: false,
+        }
+    }
+
+    /// Parses the groups in parentheses, like the operators in `+COPS: (2,"A","A","20401",7),(1,"B","B","20402",2)`.
+    ///
+    /// The returned iterator gives the result of the `parse` closure for every group, one at a time.
+    /// The closure gets a parser that starts after the `(` and ends before the `)`.
+    /// Anything it doesn't parse at the end of the group is ignored.
+    /// The groups are separated by commas, and the iteration stops at the first parameter that isn't a group.
+    ///
+    /// After a failing group, the iteration stops.
+    /// [Repeated::into_parser] gives back a parser that continues after the groups.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    ///
+    /// let mut operators = CommandParser::parse(
+    ///     b"+COPS: (2,\"Operator A\",\"A\",\"20401\",7),(1,\"Operator B\",\"B\",\"20402\",2),,(0-4),(0-2)\r\n",
+    /// )
+    /// .expect_identifier(b"+COPS:")
+    /// .repeat_groups(|parser| {
+    ///     parser
+    ///         .expect_int_parameter()
+    ///         .expect_string_parameter()
+    ///         .expect_string_parameter()
+    ///         .expect_string_parameter()
+    ///         .expect_int_parameter()
+    /// });
+    ///
+    /// assert_eq!(operators.next(), Some(Ok((2, "Operator A", "A", "20401", 7))));
+    /// assert_eq!(operators.next(), Some(Ok((1, "Operator B", "B", "20402", 2))));
+    /// assert_eq!(operators.next(), None);
+    ///
+    /// let (modes, formats) = operators
+    ///     .into_parser()
+    ///     .expect_identifier(b",")
+    ///     .expect_range_list_parameter()
+    ///     .expect_range_list_parameter()
+    ///     .finish()
+    ///     .unwrap();
+    /// assert!(modes.contains(4));
+    /// assert!(formats.contains(2));
+    /// ```
+    pub fn repeat_groups<T, F>(self, parse: F) -> Repeated<'a, D, F>
+    where
+        F: FnMut(CommandParser<'a, ()>) -> CommandParser<'a, T>,
+    {
+        Repeated {
+            parser: self,
+            repetition: Repetition::Groups,
+            parse,
+            done: false,
+        }
+    }
+}
+
+/// What is repeated
+#[derive(Debug, Clone, Copy)]
+enum Repetition<'a> {
+    /// Lines that start with the prefix
+    Lines(&'a [u8]),
+    /// Lines that start with the prefix and the lines after them
+    Entries(&'a [u8]),
+    /// Groups in parentheses
+    Groups,
+}
+
+/// Iterator over the repeated lines or groups of a response.
+///
+/// Created by [CommandParser::repeat_lines], [CommandParser::repeat_entries]
+/// and [CommandParser::repeat_groups].
+#[must_use]
+pub struct Repeated<'a, D, F> {
+    parser: CommandParser<'a, D>,
+    repetition: Repetition<'a>,
+    parse: F,
+    done: bool,
+}
+
+impl<'a, D, F> Repeated<'a, D, F> {
+    /// Finds the next line or group.
+    ///
+    /// Returns the start and end of what the sub-parser gets and the index after the line or group.
+    fn find_next(&self) -> Option<Result<(usize, usize, usize), ParseError<'a>>> {
+        let buffer = self.parser.buffer;
+        let index = self.parser.buffer_index;
+
+        match self.repetition {
+            Repetition::Lines(prefix) => {
+                let (line_start, line_end) = find_line(buffer, index);
+
+                // Even an empty prefix doesn't match after the last line
+                if line_start == buffer.len() || !buffer[line_start..].starts_with(prefix) {
+                    return None;
+                }
+
+                Some(Ok((line_start + prefix.len(), line_end, line_end)))
             }
-            .trim_space();
-        } else {
-            return CommandParser {
-                buffer: self.buffer,
-                buffer_index,
-                data_valid: false,
-                data: self.data.tup_cat(""),
+            Repetition::Entries(prefix) => {
+                let (line_start, mut entry_end) = find_line(buffer, index);
+
+                if line_start == buffer.len() || !buffer[line_start..].starts_with(prefix) {
+                    return None;
+                }
+
+                // Take the lines of the body until the next entry or the final result code
+                loop {
+                    let (line_start, line_end) = find_line(buffer, entry_end);
+                    let line = &buffer[line_start..line_end];
+
+                    if line_start == buffer.len()
+                        || line.starts_with(prefix)
+                        || FinalResult::parse_line(line).is_some()
+                    {
+                        break;
+                    }
+                    entry_end = line_end;
+                }
+
+                Some(Ok((line_start + prefix.len(), entry_end, entry_end)))
+            }
+            Repetition::Groups => {
+                if buffer.get(index) != Some(&b'(') {
+                    return None;
+                }
+
+                // Find the closing parenthesis that is not in a string
+                let mut in_string = false;
+                let group_end = buffer[index..].iter().position(|byte| {
+                    in_string ^= *byte == b'"';
+                    *byte == b')' && !in_string
+                });
+
+                let Some(group_end) = group_end.map(|group_end| index + group_end) else {
+                    return Some(Err(ParseError {
+                        kind: ParseErrorKind::UnexpectedEnd,
+                        expected: Expected::Identifier(b")"),
+                        index,
+                    }));
+                };
+
+                // Skip the parenthesis and the separator (comma) if it's there
+                let next_index =
+                    group_end + 1 + (buffer.get(group_end + 1) == Some(&b',')) as usize;
+
+                Some(Ok((index + 1, group_end, next_index)))
             }
-            .trim_space();
         }
     }
 
-    /// Tries reading a non-parameter, non-quoted string
-    pub fn expect_raw_string(self) -> CommandParser<'a, D::Out> {
-        // If we're already not valid, then quit
-        if !self.data_valid {
-            return CommandParser {
-                buffer: self.buffer,
-                buffer_index: self.buffer_index,
-                data_valid: self.data_valid,
-                data: self.data.tup_cat(""),
-            };
+    /// Gives back the parser after the repeated lines or groups.
+    ///
+    /// The lines or groups that weren't iterated over yet are parsed and skipped.
+    /// If one of them failed, the parser has that error.
+    pub fn into_parser<T>(mut self) -> CommandParser<'a, D>
+    where
+        F: FnMut(CommandParser<'a, ()>) -> CommandParser<'a, T>,
+    {
+        for _ in self.by_ref() {}
+        self.parser
+    }
+}
+
+/// Finds the start and end of the line at the index, skipping the line terminators before it.
+fn find_line(buffer: &[u8], index: usize) -> (usize, usize) {
+    let line_start = index
+        + buffer[index..]
+            .iter()
+            .take_while(|byte| is_line_terminator(**byte))
+            .count();
+
+    let line_end = line_start
+        + buffer[line_start..]
+            .iter()
+            .position(|byte| is_line_terminator(*byte))
+            .unwrap_or(buffer.len() - line_start);
+
+    (line_start, line_end)
+}
+
+impl<'a, D, T, F> Iterator for Repeated<'a, D, F>
+where
+    F: FnMut(CommandParser<'a, ()>) -> CommandParser<'a, T>,
+{
+    type Item = Result<T, ParseError<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.parser.data_valid.is_err() {
+            return None;
         }
 
-        let (buffer_index, data_valid, data) = self.parse_raw_string_parameter();
-        if let Some(parameter_value) = data {
-            return CommandParser {
-                buffer: self.buffer,
-                buffer_index,
-                data_valid,
-                data: self.data.tup_cat(parameter_value),
+        let result = match self.find_next() {
+            None => {
+                self.done = true;
+                return None;
             }
-            .trim_space();
-        } else {
-            return CommandParser {
-                buffer: self.buffer,
-                buffer_index,
-                data_valid: false,
-                data: self.data.tup_cat(""),
+            Some(Ok((start, end, next_index))) => {
+                let sub_parser = CommandParser {
+                    buffer: &self.parser.buffer[..end],
+                    buffer_index: start,
+                    data_valid: Ok(()),
+                    data: (),
+                }
+                .trim_space();
+
+                let result = (self.parse)(sub_parser).finish();
+                if result.is_ok() {
+                    // Continue after the line or group and the space after it
+                    self.parser.buffer_index = next_index
+                        + self.parser.buffer[next_index..]
+                            .iter()
+                            .take_while(|byte| **byte == b' ')
+                            .count();
+                }
+                result
             }
-            .trim_space();
+            Some(Err(error)) => Err(error),
+        };
+
+        if let Err(error) = &result {
+            self.parser.data_valid = Err(error.clone());
         }
+
+        Some(result)
     }
 }
 
-//
-// Optional parameters
-//
+impl<'a, D: TupleConcat<i32>> CommandParser<'a, D> {
+    /// Tries reading an int parameter
+    pub fn expect_int_parameter(self) -> CommandParser<'a, D::Out> {
+        self.expect_int_parameter_as()
+    }
+}
 
 impl<'a, D: TupleConcat<Option<i32>>> CommandParser<'a, D> {
     /// Tries reading an int parameter
     pub fn expect_optional_int_parameter(self) -> CommandParser<'a, D::Out> {
+        self.expect_optional_int_parameter_as()
+    }
+}
+
+impl<'a, D> CommandParser<'a, D> {
+    /// Tries reading an int parameter of the given [Integer] type.
+    ///
+    /// A value that doesn't fit in the type is an error.
+    ///
+    /// ```
+    /// use at_commands::parser::{CommandParser, ParseErrorKind};
+    ///
+    /// let (cell_id, bytes) = CommandParser::parse(b"+CELL: 4294967295,18446744073709551615\r\n")
+    ///     .expect_identifier(b"+CELL:")
+    ///     .expect_int_parameter_as::<u32>()
+    ///     .expect_int_parameter_as::<u64>()
+    ///     .expect_identifier(b"\r\n")
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(cell_id, u32::MAX);
+    /// assert_eq!(bytes, u64::MAX);
+    ///
+    /// let error = CommandParser::parse(b"256")
+    ///     .expect_int_parameter_as::<u8>()
+    ///     .finish()
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(error.kind, ParseErrorKind::IntOverflow);
+    /// ```
+    pub fn expect_int_parameter_as<I: Integer>(
+        self,
+    ) -> CommandParser<'a, <D as TupleConcat<I>>::Out>
+    where
+        D: TupleConcat<I>,
+    {
         // If we're already not valid, then quit
-        if !self.data_valid {
-            return CommandParser {
-                buffer: self.buffer,
-                buffer_index: self.buffer_index,
-                data_valid: self.data_valid,
-                data: self.data.tup_cat(None),
-            };
+        if self.data_valid.is_err() {
+            return self.skip_parameter(I::default());
         }
 
-        let (buffer_index, data_valid, data) = self.parse_int_parameter();
-        return CommandParser {
-            buffer: self.buffer,
-            buffer_index,
-            data_valid,
-            data: self.data.tup_cat(data),
+        let (buffer_index, result) = self.parse_int_parameter();
+        let result = result.and_then(|value| {
+            value.ok_or_else(|| self.missing_parameter_kind(ParseErrorKind::InvalidInt))
+        });
+        self.push_parameter(Expected::IntParameter, I::default(), (buffer_index, result))
+    }
+
+    /// Tries reading an optional int parameter of the given [Integer] type.
+    ///
+    /// See [Self::expect_int_parameter_as].
+    pub fn expect_optional_int_parameter_as<I: Integer>(
+        self,
+    ) -> CommandParser<'a, <D as TupleConcat<Option<I>>>::Out>
+    where
+        D: TupleConcat<Option<I>>,
+    {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(None);
+        }
+
+        let result = self.parse_int_parameter();
+        self.push_parameter(Expected::IntParameter, None, result)
+    }
+}
+
+impl<'a, D: TupleConcat<&'a str>> CommandParser<'a, D> {
+    /// Tries reading a string parameter
+    pub fn expect_string_parameter(self) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter("");
+        }
+
+        let (buffer_index, result) = self.parse_string_parameter();
+        let result = result.and_then(|value| {
+            value.ok_or_else(|| self.missing_parameter_kind(ParseErrorKind::UnterminatedString))
+        });
+        self.push_parameter(Expected::StringParameter, "", (buffer_index, result))
+    }
+
+    /// Tries reading a non-parameter, non-quoted string
+    pub fn expect_raw_string(self) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter("");
+        }
+
+        let (buffer_index, result) = self.parse_raw_string_parameter();
+        // A raw string is never missing, at worst it's empty
+        let result = result.map(|value| value.unwrap_or(""));
+        self.push_parameter(Expected::RawString, "", (buffer_index, result))
+    }
+}
+
+impl<'a, D> CommandParser<'a, D> {
+    /// Tries reading an int parameter that is written as hex digits, like `80084`.
+    ///
+    /// The [Integer] type must be given.
+    /// Values with more bits than the type are an error.
+    /// Signed types get the bits of the value, so `FF` is `-1i8`.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    ///
+    /// let (band, mask) = CommandParser::parse(b"+QCFG: \"band\",80084\r\n")
+    ///     .expect_identifier(b"+QCFG:")
+    ///     .expect_string_parameter()
+    ///     .expect_hex_parameter::<u32>()
+    ///     .expect_identifier(b"\r\n")
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(band, "band");
+    /// assert_eq!(mask, 0x80084);
+    /// ```
+    pub fn expect_hex_parameter<I: Integer>(self) -> CommandParser<'a, <D as TupleConcat<I>>::Out>
+    where
+        D: TupleConcat<I>,
+    {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(I::default());
         }
-        .trim_space();
+
+        let (buffer_index, result) = self.parse_hex_parameter();
+        let result = result.and_then(|value| {
+            value.ok_or_else(|| self.missing_parameter_kind(ParseErrorKind::InvalidInt))
+        });
+        self.push_parameter(Expected::HexParameter, I::default(), (buffer_index, result))
+    }
+
+    /// Tries reading an int parameter that is written as hex digits between quotes, like `"1A2B"`.
+    ///
+    /// See [Self::expect_hex_parameter].
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    ///
+    /// let (n, stat, lac, ci) = CommandParser::parse(b"+CREG: 2,1,\"1A2B\",\"01CD2E3F\"\r\n")
+    ///     .expect_identifier(b"+CREG:")
+    ///     .expect_int_parameter()
+    ///     .expect_int_parameter()
+    ///     .expect_quoted_hex_parameter::<u16>()
+    ///     .expect_quoted_hex_parameter::<u32>()
+    ///     .expect_identifier(b"\r\n")
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!((n, stat), (2, 1));
+    /// assert_eq!(lac, 0x1A2B);
+    /// assert_eq!(ci, 0x01CD2E3F);
+    /// ```
+    pub fn expect_quoted_hex_parameter<I: Integer>(
+        self,
+    ) -> CommandParser<'a, <D as TupleConcat<I>>::Out>
+    where
+        D: TupleConcat<I>,
+    {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(I::default());
+        }
+
+        let (buffer_index, result) = self.parse_quoted_hex_parameter();
+        let result = result.and_then(|value| {
+            value.ok_or_else(|| self.missing_quoted_parameter_kind(ParseErrorKind::InvalidInt))
+        });
+        self.push_parameter(Expected::HexParameter, I::default(), (buffer_index, result))
+    }
+
+    /// Tries reading an optional int parameter that is written as hex digits.
+    ///
+    /// See [Self::expect_hex_parameter].
+    pub fn expect_optional_hex_parameter<I: Integer>(
+        self,
+    ) -> CommandParser<'a, <D as TupleConcat<Option<I>>>::Out>
+    where
+        D: TupleConcat<Option<I>>,
+    {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(None);
+        }
+
+        let result = self.parse_hex_parameter();
+        self.push_parameter(Expected::HexParameter, None, result)
+    }
+
+    /// Tries reading an optional int parameter that is written as hex digits between quotes.
+    /// An empty `""` is read as `None` too.
+    ///
+    /// See [Self::expect_hex_parameter].
+    pub fn expect_optional_quoted_hex_parameter<I: Integer>(
+        self,
+    ) -> CommandParser<'a, <D as TupleConcat<Option<I>>>::Out>
+    where
+        D: TupleConcat<Option<I>>,
+    {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(None);
+        }
+
+        let result = self.parse_quoted_hex_parameter();
+        self.push_parameter(Expected::HexParameter, None, result)
+    }
+}
+
+impl<'a, D: TupleConcat<u8>> CommandParser<'a, D> {
+    /// Tries reading the value of an S-register, like the `002` the device answers to `ATS0?` with.
+    ///
+    /// The line terminators before the value are skipped.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    /// use at_commands::result_code::FinalResult;
+    ///
+    /// let (rings, result) = CommandParser::parse(b"\r\n002\r\n\r\nOK\r\n")
+    ///     .expect_s_register_value()
+    ///     .expect_final_result()
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(rings, 2);
+    /// assert_eq!(result, FinalResult::Ok);
+    /// ```
+    pub fn expect_s_register_value(mut self) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(0);
+        }
+
+        // Skip the line terminators before the value
+        self.buffer_index += self.buffer[self.buffer_index..]
+            .iter()
+            .take_while(|byte| is_line_terminator(**byte))
+            .count();
+
+        let (buffer_index, result) = self.parse_int_parameter();
+        let result = result.and_then(|value| {
+            value.ok_or_else(|| self.missing_parameter_kind(ParseErrorKind::InvalidInt))
+        });
+        self.push_parameter(Expected::IntParameter, 0, (buffer_index, result))
     }
 }
 
+impl<'a, D: TupleConcat<&'a [u8]>> CommandParser<'a, D> {
+    /// Tries reading the separator and then exactly `length` bytes of raw data.
+    ///
+    /// The data can contain anything, including line terminators and bytes that aren't UTF-8.
+    /// No space is skipped between the separator and the data.
+    ///
+    /// See [Self::expect_length_prefixed_data] for when the length is the previous parameter.
+    pub fn expect_data(self, separator: &'a [u8], length: usize) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(&[]);
+        }
+
+        if let Err(kind) = self.compare_identifier(separator) {
+            return self
+                .skip_parameter(&[][..])
+                .fail(kind, Expected::Identifier(separator));
+        }
+
+        let result = self.parse_data(separator, length);
+        self.push_parameter(Expected::Data, &[], result)
+    }
+}
+
+impl<'a, D> CommandParser<'a, D>
+where
+    D: TupleConcat<&'a [u8]> + TupleLast,
+    D::Last: Integer,
+{
+    /// Tries reading the separator and then the raw data,
+    /// of which the length is the int parameter that was parsed last.
+    ///
+    /// This is for responses like `+IPD,0,12:<12 bytes>` or `+QIRD: 12\r\n<12 bytes>`.
+    /// The data can contain anything, including line terminators and bytes that aren't UTF-8.
+    /// No space is skipped between the separator and the data.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    ///
+    /// let (link, length, data) = CommandParser::parse(b"+IPD,0,6:\r\nOK\r\xFF\r\nOK\r\n")
+    ///     .expect_identifier(b"+IPD,")
+    ///     .expect_int_parameter()
+    ///     .expect_int_parameter()
+    ///     .expect_length_prefixed_data(b":")
+    ///     .expect_identifier(b"\r\nOK\r\n")
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!((link, length), (0, 6));
+    /// assert_eq!(data, b"\r\nOK\r\xFF");
+    /// ```
+    pub fn expect_length_prefixed_data(
+        self,
+        separator: &'a [u8],
+    ) -> CommandParser<'a, <D as TupleConcat<&'a [u8]>>::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(&[]);
+        }
+
+        match crate::formatter::to_usize(self.data.last()) {
+            Some(length) => self.expect_data(separator, length),
+            None => self
+                .skip_parameter(&[][..])
+                .fail(ParseErrorKind::InvalidInt, Expected::Data),
+        }
+    }
+}
+
+impl<'a, 'b, D: TupleConcat<&'b [u8]>> CommandParser<'a, D> {
+    /// Tries reading bytes that are written as hex digits, like `A0B1C2`.
+    ///
+    /// The bytes are decoded into the given buffer, which must be big enough to contain them.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    ///
+    /// let mut data = [0; 16];
+    ///
+    /// let (socket, length, payload) = CommandParser::parse(b"+NSORF: 0,4,DEAD0BEF\r\n")
+    ///     .expect_identifier(b"+NSORF:")
+    ///     .expect_int_parameter()
+    ///     .expect_int_parameter()
+    ///     .expect_hex_bytes_parameter(&mut data)
+    ///     .expect_identifier(b"\r\n")
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!((socket, length), (0, 4));
+    /// assert_eq!(payload, [0xDE, 0xAD, 0x0B, 0xEF]);
+    /// ```
+    pub fn expect_hex_bytes_parameter(
+        self,
+        bytes_buffer: &'b mut [u8],
+    ) -> CommandParser<'a, D::Out> {
+        self.expect_hex_bytes(false, bytes_buffer)
+    }
+
+    /// Tries reading bytes that are written as hex digits between quotes, like `"A0B1C2"`.
+    ///
+    /// See [Self::expect_hex_bytes_parameter].
+    pub fn expect_quoted_hex_bytes_parameter(
+        self,
+        bytes_buffer: &'b mut [u8],
+    ) -> CommandParser<'a, D::Out> {
+        self.expect_hex_bytes(true, bytes_buffer)
+    }
+
+    fn expect_hex_bytes(
+        self,
+        quoted: bool,
+        bytes_buffer: &'b mut [u8],
+    ) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(&[]);
+        }
+
+        let (buffer_index, result) = self.parse_hex_bytes_parameter(quoted, bytes_buffer);
+        let result = result.and_then(|value| {
+            value.ok_or_else(|| {
+                if quoted {
+                    self.missing_quoted_parameter_kind(ParseErrorKind::InvalidHex)
+                } else {
+                    self.missing_parameter_kind(ParseErrorKind::InvalidHex)
+                }
+            })
+        });
+        self.push_parameter(Expected::HexParameter, &[], (buffer_index, result))
+    }
+}
+
+impl<'a, 'b, D: TupleConcat<&'b str>> CommandParser<'a, D> {
+    /// Tries reading a string parameter in which the special characters are escaped.
+    ///
+    /// The unescaped string is written to the given buffer, which must be big enough to contain it.
+    /// See [Escaping] for which characters are escaped and how.
+    ///
+    /// ```
+    /// use at_commands::escape::Escaping;
+    /// use at_commands::parser::CommandParser;
+    ///
+    /// let mut ssid_buffer = [0; 32];
+    ///
+    /// let (ssid, channel) = CommandParser::parse(b"+CWJAP:\"my\\\"wifi\\,\",1\r\nOK\r\n")
+    ///     .expect_identifier(b"+CWJAP:")
+    ///     .expect_escaped_string_parameter(Escaping::Backslash, &mut ssid_buffer)
+    ///     .expect_int_parameter()
+    ///     .expect_identifier(b"\r\nOK\r\n")
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(ssid, "my\"wifi,");
+    /// assert_eq!(channel, 1);
+    /// ```
+    pub fn expect_escaped_string_parameter(
+        self,
+        escaping: Escaping,
+        string_buffer: &'b mut [u8],
+    ) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter("");
+        }
+
+        let (buffer_index, result) = self.parse_escaped_string_parameter(escaping, string_buffer);
+        let result = result.and_then(|value| {
+            value.ok_or_else(|| self.missing_parameter_kind(ParseErrorKind::UnterminatedString))
+        });
+        self.push_parameter(Expected::StringParameter, "", (buffer_index, result))
+    }
+}
+
+impl<'a, D: TupleConcat<RangeList<'a>>> CommandParser<'a, D> {
+    /// Tries reading a list of supported values, like the `(0,1,4)` or `(0-3)` that test commands answer with.
+    ///
+    /// See [RangeList] for an example.
+    pub fn expect_range_list_parameter(self) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(RangeList::default());
+        }
+
+        let (buffer_index, result) = self.parse_range_list_parameter();
+        let result = result.and_then(|value| {
+            value.ok_or_else(|| self.missing_parameter_kind(ParseErrorKind::InvalidRangeList))
+        });
+        self.push_parameter(
+            Expected::RangeList,
+            RangeList::default(),
+            (buffer_index, result),
+        )
+    }
+}
+
+impl<'a, D: TupleConcat<Option<RangeList<'a>>>> CommandParser<'a, D> {
+    /// Tries reading a list of supported values that may be left out, like the second one in `+COPS: (0-4),,(0-2)`.
+    pub fn expect_optional_range_list_parameter(self) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(None);
+        }
+
+        let result = self.parse_range_list_parameter();
+        self.push_parameter(Expected::RangeList, None, result)
+    }
+}
+
+impl<'a, D: TupleConcat<FinalResult<'a>>> CommandParser<'a, D> {
+    /// Tries reading a final result code, like `OK`, `ERROR` or `+CME ERROR: 10`.
+    ///
+    /// The line terminators around it are skipped,
+    /// so this can end a chain instead of `expect_identifier(b"\r\nOK\r\n")`.
+    /// An error result code is not a parse error, it is returned like any other parameter.
+    ///
+    /// ```
+    /// use at_commands::parser::CommandParser;
+    /// use at_commands::result_code::{CmeError, FinalResult};
+    ///
+    /// let (rssi, ber, result) = CommandParser::parse(b"+CSQ: 20,99\r\nOK\r\n")
+    ///     .expect_identifier(b"+CSQ:")
+    ///     .expect_int_parameter()
+    ///     .expect_int_parameter()
+    ///     .expect_final_result()
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!((rssi, ber), (20, 99));
+    /// assert_eq!(result, FinalResult::Ok);
+    ///
+    /// let (result,) = CommandParser::parse(b"\r\n+CME ERROR: 10\r\n")
+    ///     .expect_final_result()
+    ///     .finish()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(result, FinalResult::CmeError(CmeError::Code(10)));
+    /// ```
+    pub fn expect_final_result(self) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(FinalResult::Error);
+        }
+
+        let result = self.parse_final_result();
+        self.push_parameter(Expected::FinalResult, FinalResult::Error, result)
+    }
+}
+
+//
+// Optional parameters
+//
+
 impl<'a, D: TupleConcat<Option<&'a str>>> CommandParser<'a, D> {
     /// Tries reading a string parameter
     pub fn expect_optional_string_parameter(self) -> CommandParser<'a, D::Out> {
         // If we're already not valid, then quit
-        if !self.data_valid {
-            return CommandParser {
-                buffer: self.buffer,
-                buffer_index: self.buffer_index,
-                data_valid: self.data_valid,
-                data: self.data.tup_cat(None),
-            };
+        if self.data_valid.is_err() {
+            return self.skip_parameter(None);
         }
 
-        let (buffer_index, data_valid, data) = self.parse_string_parameter();
-        return CommandParser {
-            buffer: self.buffer,
-            buffer_index,
-            data_valid,
-            data: self.data.tup_cat(data),
-        }
-        .trim_space();
+        let result = self.parse_string_parameter();
+        self.push_parameter(Expected::StringParameter, None, result)
     }
 
     /// Tries reading a non-parameter, non-quoted string
     pub fn expect_optional_raw_string(self) -> CommandParser<'a, D::Out> {
         // If we're already not valid, then quit
-        if !self.data_valid {
-            return CommandParser {
-                buffer: self.buffer,
-                buffer_index: self.buffer_index,
-                data_valid: self.data_valid,
-                data: self.data.tup_cat(None),
-            };
+        if self.data_valid.is_err() {
+            return self.skip_parameter(None);
         }
 
-        let (buffer_index, data_valid, data) = self.parse_raw_string_parameter();
-        return CommandParser {
-            buffer: self.buffer,
-            buffer_index,
-            data_valid,
-            data: self.data.tup_cat(data),
+        let result = self.parse_raw_string_parameter();
+        self.push_parameter(Expected::RawString, None, result)
+    }
+}
+
+impl<'a, 'b, D: TupleConcat<Option<&'b str>>> CommandParser<'a, D> {
+    /// Tries reading a string parameter in which the special characters are escaped.
+    ///
+    /// See [Self::expect_escaped_string_parameter].
+    pub fn expect_optional_escaped_string_parameter(
+        self,
+        escaping: Escaping,
+        string_buffer: &'b mut [u8],
+    ) -> CommandParser<'a, D::Out> {
+        // If we're already not valid, then quit
+        if self.data_valid.is_err() {
+            return self.skip_parameter(None);
         }
-        .trim_space();
+
+        let result = self.parse_escaped_string_parameter(escaping, string_buffer);
+        self.push_parameter(Expected::StringParameter, None, result)
     }
 }
 
 /// Error type for parsing
-///
-/// The number is the index of up to where it was correctly parsed
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ParseError<'a> {
+    /// What went wrong
+    pub kind: ParseErrorKind,
+    /// What the failing step was trying to read
+    pub expected: Expected<'a>,
+    /// The index of up to where it was correctly parsed.
+    /// This is where the failing step started.
+    pub index: usize,
+}
+
+/// The reason a parse step failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct ParseError(usize);
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// The data did not match the expected identifier
+    IdentifierMismatch,
+    /// The parameter could not be parsed as an int
+    InvalidInt,
+    /// The closing quote of a string parameter is missing
+    UnterminatedString,
+    /// The string is not valid UTF-8
+    InvalidUtf8,
+    /// The data ended before the step could complete
+    UnexpectedEnd,
+    /// The line is not a known final result code
+    UnknownFinalResult,
+    /// The int parameter does not fit in the requested type
+    IntOverflow,
+    /// A string parameter contains an escape sequence that is not valid
+    InvalidEscape,
+    /// The buffer given to hold the parameter is too small
+    BufferTooSmall,
+    /// The hex data has an odd length or a character that is not a hex digit
+    InvalidHex,
+    /// The parameter is not a list of values and ranges in parentheses
+    InvalidRangeList,
+}
+
+/// The thing a parse step was trying to read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Expected<'a> {
+    /// The given identifier
+    Identifier(&'a [u8]),
+    /// An int parameter
+    IntParameter,
+    /// A hex parameter, quoted or not
+    HexParameter,
+    /// Raw data of a known length
+    Data,
+    /// The `>` prompt
+    Prompt,
+    /// The end of a line
+    LineBreak,
+    /// A list of values and ranges in parentheses
+    RangeList,
+    /// A quoted string parameter
+    StringParameter,
+    /// A non-quoted string
+    RawString,
+    /// A final result code
+    FinalResult,
+    /// The name of an incoming command
+    CommandName,
+}
 
 #[cfg(test)]
 mod tests {
@@ -565,7 +1716,14 @@ mod tests {
             .expect_optional_identifier(b"\r\nOK\r\n")
             .finish();
 
-        assert_eq!(r, Err(ParseError(20)));
+        assert_eq!(
+            r,
+            Err(ParseError {
+                kind: ParseErrorKind::IdentifierMismatch,
+                expected: Expected::Identifier(b"\r\nOK\r\n"),
+                index: 20,
+            })
+        );
 
         let (x, y, z) = CommandParser::parse(b"+SYSGPIOREAD:,\"true\"\r\nOK\r\n")
             .expect_identifier(b"+SYSGPIOREAD:")
@@ -593,4 +1751,758 @@ mod tests {
         assert_eq!(y, Some("true"));
         assert_eq!(z, None);
     }
+
+    #[test]
+    fn test_optional_string_parameter_middle_not_present() {
+        let (x, y, z) = CommandParser::parse(b"+CGDCONT: 1,,0\r\nOK\r\n")
+            .expect_identifier(b"+CGDCONT:")
+            .expect_optional_int_parameter()
+            .expect_optional_string_parameter()
+            .expect_optional_int_parameter()
+            .expect_identifier(b"\r\nOK\r\n")
+            .finish()
+            .unwrap();
+
+        assert_eq!(x, Some(1));
+        assert_eq!(y, None);
+        assert_eq!(z, Some(0));
+    }
+
+    #[test]
+    fn test_escaped_string_parameter() {
+        let mut first = [0; 32];
+        let mut second = [0; 32];
+        let mut third = [0; 32];
+
+        let (url, path, host, number) =
+            CommandParser::parse(b"+URL: \"a\\\"b\\,c\",\"C:\\5C\\22x\\22\",,5\r\nOK\r\n")
+                .expect_identifier(b"+URL:")
+                .expect_escaped_string_parameter(Escaping::Backslash, &mut first)
+                .expect_escaped_string_parameter(Escaping::Hex, &mut second)
+                .expect_optional_escaped_string_parameter(Escaping::Hex, &mut third)
+                .expect_int_parameter()
+                .expect_identifier(b"\r\nOK\r\n")
+                .finish()
+                .unwrap();
+
+        assert_eq!(url, "a\"b,c");
+        assert_eq!(path, "C:\\\"x\"");
+        assert_eq!(host, None);
+        assert_eq!(number, 5);
+
+        // Without escaping, the first quote ends the string
+        let (value,) = CommandParser::parse(b"\"a\\\"")
+            .expect_string_parameter()
+            .finish()
+            .unwrap();
+        assert_eq!(value, "a\\");
+
+        let mut small = [0; 2];
+        assert_eq!(
+            CommandParser::parse(b"\"abc\"")
+                .expect_escaped_string_parameter(Escaping::Backslash, &mut small)
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::BufferTooSmall,
+                expected: Expected::StringParameter,
+                index: 0,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"\"\\zz\"")
+                .expect_escaped_string_parameter(Escaping::Hex, &mut first)
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::InvalidEscape
+        );
+    }
+
+    #[test]
+    fn test_hex_parameters() {
+        let (stat, lac, ci, act, mask) =
+            CommandParser::parse(b"+CEREG: 1,\"1a2b\",,7,FF\r\nOK\r\n")
+                .expect_identifier(b"+CEREG:")
+                .expect_int_parameter()
+                .expect_optional_quoted_hex_parameter::<u16>()
+                .expect_optional_quoted_hex_parameter::<u32>()
+                .expect_optional_hex_parameter::<u8>()
+                .expect_hex_parameter::<i8>()
+                .expect_identifier(b"\r\nOK\r\n")
+                .finish()
+                .unwrap();
+
+        assert_eq!(stat, 1);
+        assert_eq!(lac, Some(0x1A2B));
+        assert_eq!(ci, None);
+        assert_eq!(act, Some(7));
+        assert_eq!(mask, -1);
+
+        assert_eq!(
+            CommandParser::parse(b"\"12345\"")
+                .expect_quoted_hex_parameter::<u16>()
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::IntOverflow,
+                expected: Expected::HexParameter,
+                index: 0,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"\"12G\"")
+                .expect_quoted_hex_parameter::<u16>()
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::InvalidInt
+        );
+        assert_eq!(
+            CommandParser::parse(b"")
+                .expect_hex_parameter::<u16>()
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::UnexpectedEnd
+        );
+
+        let (lac, ci) = CommandParser::parse(b"\"\",\r\n")
+            .expect_optional_quoted_hex_parameter::<u16>()
+            .expect_optional_hex_parameter::<u32>()
+            .expect_identifier(b"\r\n")
+            .finish()
+            .unwrap();
+        assert_eq!((lac, ci), (None, None));
+        assert_eq!(
+            CommandParser::parse(b"\"\"\r\n")
+                .expect_quoted_hex_parameter::<u16>()
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::InvalidInt
+        );
+        assert_eq!(
+            CommandParser::parse(b"\"12")
+                .expect_quoted_hex_parameter::<u16>()
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::UnterminatedString
+        );
+    }
+
+    #[test]
+    fn test_hex_bytes_parameters() {
+        let mut first = [0; 8];
+        let mut second = [0; 8];
+
+        let (length, first, second) = CommandParser::parse(b"+CSIM: 4,\"9000\",00ff\r\nOK\r\n")
+            .expect_identifier(b"+CSIM:")
+            .expect_int_parameter()
+            .expect_quoted_hex_bytes_parameter(&mut first)
+            .expect_hex_bytes_parameter(&mut second)
+            .expect_identifier(b"\r\nOK\r\n")
+            .finish()
+            .unwrap();
+
+        assert_eq!(length, 4);
+        assert_eq!(first, [0x90, 0x00]);
+        assert_eq!(second, [0x00, 0xFF]);
+
+        let mut small = [0; 1];
+        assert_eq!(
+            CommandParser::parse(b"\"9000\"")
+                .expect_quoted_hex_bytes_parameter(&mut small)
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::BufferTooSmall,
+                expected: Expected::HexParameter,
+                index: 0,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"900\r\n")
+                .expect_hex_bytes_parameter(&mut small)
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::InvalidHex
+        );
+        assert_eq!(
+            CommandParser::parse(b"\r\n")
+                .expect_hex_bytes_parameter(&mut small)
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::InvalidHex
+        );
+        assert_eq!(
+            CommandParser::parse(b"\"\"\r\n")
+                .expect_quoted_hex_bytes_parameter(&mut small)
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::InvalidHex
+        );
+    }
+
+    #[test]
+    fn test_length_prefixed_data() {
+        let (length, data) = CommandParser::parse(b"+QIRD: 4\r\n \r\n\0\r\nOK\r\n")
+            .expect_identifier(b"+QIRD:")
+            .expect_int_parameter_as::<u16>()
+            .expect_length_prefixed_data(b"\r\n")
+            .expect_final_result()
+            .finish()
+            .map(|(length, data, _)| (length, data))
+            .unwrap();
+        assert_eq!(length, 4);
+        assert_eq!(data, b" \r\n\0");
+
+        let (socket, length, data) = CommandParser::parse(b"+USORD: 0,5,\"a\"b,c\"\r\n")
+            .expect_identifier(b"+USORD:")
+            .expect_int_parameter()
+            .expect_int_parameter()
+            .expect_length_prefixed_data(b"\"")
+            .expect_identifier(b"\"\r\n")
+            .finish()
+            .unwrap();
+        assert_eq!((socket, length), (0, 5));
+        assert_eq!(data, b"a\"b,c");
+
+        let (empty,) = CommandParser::parse(b"0:")
+            .expect_int_parameter()
+            .expect_length_prefixed_data(b":")
+            .finish()
+            .map(|(_, data)| (data,))
+            .unwrap();
+        assert_eq!(empty, b"");
+
+        let ((length,), parser) = CommandParser::parse(b"+IPD,3:abcd")
+            .expect_identifier(b"+IPD,")
+            .expect_int_parameter()
+            .take_data();
+        let (data,) = parser.expect_data(b":", length as usize).finish().unwrap();
+        assert_eq!(data, b"abc");
+    }
+
+    #[test]
+    fn test_length_prefixed_data_errors() {
+        assert_eq!(
+            CommandParser::parse(b"+IPD,12:abcd")
+                .expect_identifier(b"+IPD,")
+                .expect_int_parameter()
+                .expect_length_prefixed_data(b":")
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedEnd,
+                expected: Expected::Data,
+                index: 7,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"+IPD,1,a")
+                .expect_identifier(b"+IPD,")
+                .expect_int_parameter()
+                .expect_length_prefixed_data(b":")
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::IdentifierMismatch,
+                expected: Expected::Identifier(b":"),
+                index: 7,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"-1:")
+                .expect_int_parameter()
+                .expect_length_prefixed_data(b":")
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::InvalidInt,
+                expected: Expected::Data,
+                index: 2,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"+QIRD: 18446744073709551615\r\nabcd")
+                .expect_identifier(b"+QIRD:")
+                .expect_int_parameter_as::<u64>()
+                .expect_length_prefixed_data(b"\r\n")
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedEnd,
+                expected: Expected::Data,
+                index: 27,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b":abcd")
+                .expect_data(b":", usize::MAX)
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::UnexpectedEnd
+        );
+    }
+
+    #[test]
+    fn test_range_list_parameters() {
+        let (functionality, reset) = CommandParser::parse(b"+CFUN: (0,1,4),(0-1)\r\nOK\r\n")
+            .expect_identifier(b"+CFUN: ")
+            .expect_range_list_parameter()
+            .expect_range_list_parameter()
+            .expect_identifier(b"\r\nOK\r\n")
+            .finish()
+            .unwrap();
+        assert_eq!(functionality.raw(), b"0,1,4");
+        assert_eq!(reset.iter().collect::<Vec<_>>(), [0..=1]);
+
+        let (mode, format, operator, technology) =
+            CommandParser::parse(b"+COPS: (0-4),,(0-2),()\r\n")
+                .expect_identifier(b"+COPS: ")
+                .expect_range_list_parameter()
+                .expect_optional_range_list_parameter()
+                .expect_optional_range_list_parameter()
+                .expect_optional_range_list_parameter()
+                .expect_identifier(b"\r\n")
+                .finish()
+                .unwrap();
+        assert!(mode.contains(4));
+        assert_eq!(format, None);
+        assert!(operator.unwrap().contains(2));
+        assert!(technology.unwrap().is_empty());
+
+        assert_eq!(
+            CommandParser::parse(b"+CFUN: (0,1,4),(0-1")
+                .expect_identifier(b"+CFUN: ")
+                .expect_range_list_parameter()
+                .expect_range_list_parameter()
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedEnd,
+                expected: Expected::RangeList,
+                index: 15,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"+CFUN: (0,a)")
+                .expect_identifier(b"+CFUN: ")
+                .expect_range_list_parameter()
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::InvalidRangeList,
+                expected: Expected::RangeList,
+                index: 7,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"+CFUN: 1")
+                .expect_identifier(b"+CFUN: ")
+                .expect_range_list_parameter()
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::InvalidRangeList
+        );
+        assert_eq!(
+            CommandParser::parse(b"+CFUN: ")
+                .expect_identifier(b"+CFUN: ")
+                .expect_range_list_parameter()
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::UnexpectedEnd
+        );
+    }
+
+    #[test]
+    fn test_repeat_lines() {
+        let response =
+            b"+CMGL: 1,\"REC READ\"\r\nhello\r\n+CMGL: 2,\"REC UNREAD\"\r\nworld\r\n\r\nOK\r\n";
+
+        // Only the lines with the prefix are parsed, the text lines in between stop the iteration
+        let mut messages = CommandParser::parse(response).repeat_lines(b"+CMGL: ", |parser| {
+            parser.expect_int_parameter().expect_string_parameter()
+        });
+        assert_eq!(messages.next(), Some(Ok((1, "REC READ"))));
+        assert_eq!(messages.next(), None);
+        assert_eq!(messages.next(), None);
+
+        let (text, _) = messages
+            .into_parser()
+            .expect_line_break()
+            .expect_raw_string()
+            .expect_line_break()
+            .take_data();
+        assert_eq!(text, ("hello",));
+
+        // As entries, the text lines belong to the header before them
+        let mut messages = CommandParser::parse(response).repeat_entries(b"+CMGL: ", |parser| {
+            parser
+                .expect_int_parameter()
+                .expect_string_parameter()
+                .expect_line_break()
+                .expect_raw_string()
+        });
+        assert_eq!(messages.next(), Some(Ok((1, "REC READ", "hello"))));
+        assert_eq!(messages.next(), Some(Ok((2, "REC UNREAD", "world"))));
+        assert_eq!(messages.next(), None);
+
+        let (result,) = messages
+            .into_parser()
+            .expect_final_result()
+            .finish()
+            .unwrap();
+        assert_eq!(result, FinalResult::Ok);
+
+        // A body with multiple lines and a header without a body
+        let mut messages =
+            CommandParser::parse(b"+CMGL: 1\r\na\r\nb\r\n+CMGL: 2\r\n+CMS ERROR: 500\r\n")
+                .repeat_entries(b"+CMGL: ", |parser| {
+                    parser
+                        .expect_int_parameter()
+                        .expect_line_break()
+                        .expect_raw_string()
+                        .expect_line_break()
+                        .expect_raw_string()
+                });
+        assert_eq!(messages.next(), Some(Ok((1, "a", "b"))));
+        assert_eq!(
+            messages.next().unwrap().unwrap_err().kind,
+            ParseErrorKind::UnexpectedEnd
+        );
+
+        // No lines at all
+        let (result,) = CommandParser::parse(b"\r\nOK\r\n")
+            .repeat_lines(b"+CGDCONT: ", |parser| parser.expect_int_parameter())
+            .into_parser()
+            .expect_final_result()
+            .finish()
+            .unwrap();
+        assert_eq!(result, FinalResult::Ok);
+
+        // The lines that weren't iterated over are skipped
+        let (result,) = CommandParser::parse(b"+CPBR: 1\r\n+CPBR: 2\r\nOK\r\n")
+            .repeat_lines(b"+CPBR: ", |parser| parser.expect_int_parameter())
+            .into_parser()
+            .expect_final_result()
+            .finish()
+            .unwrap();
+        assert_eq!(result, FinalResult::Ok);
+
+        // An empty prefix matches every line, until the end of the buffer
+        let mut lines =
+            CommandParser::parse(b"").repeat_lines(b"", |parser| parser.expect_raw_string());
+        assert_eq!(lines.next(), None);
+
+        let mut lines = CommandParser::parse(b"a\r\nb\r\n")
+            .repeat_lines(b"", |parser| parser.expect_raw_string());
+        assert_eq!(lines.next(), Some(Ok(("a",))));
+        assert_eq!(lines.next(), Some(Ok(("b",))));
+        assert_eq!(lines.next(), None);
+
+        let mut entries =
+            CommandParser::parse(b"").repeat_entries(b"", |parser| parser.expect_raw_string());
+        assert_eq!(entries.next(), None);
+
+        let mut entries = CommandParser::parse(b"a\r\nb")
+            .repeat_entries(b"", |parser| parser.expect_raw_string());
+        assert_eq!(entries.next(), Some(Ok(("a",))));
+        assert_eq!(entries.next(), Some(Ok(("b",))));
+        assert_eq!(entries.next(), None);
+    }
+
+    #[test]
+    fn test_repeat_errors() {
+        let mut lines = CommandParser::parse(b"+CPBR: 1\r\n+CPBR: x\r\n+CPBR: 3\r\nOK\r\n")
+            .repeat_lines(b"+CPBR: ", |parser| parser.expect_int_parameter());
+        assert_eq!(lines.next(), Some(Ok((1,))));
+
+        let error = ParseError {
+            kind: ParseErrorKind::InvalidInt,
+            expected: Expected::IntParameter,
+            index: 17,
+        };
+        assert_eq!(lines.next(), Some(Err(error.clone())));
+        assert_eq!(lines.next(), None);
+        assert_eq!(lines.into_parser().finish(), Err(error));
+
+        // The sub-parser can't read past the end of the line
+        assert_eq!(
+            CommandParser::parse(b"+CPBR: 1\r\n2")
+                .repeat_lines(b"+CPBR: ", |parser| parser
+                    .expect_int_parameter()
+                    .expect_int_parameter())
+                .next()
+                .unwrap()
+                .unwrap_err()
+                .index,
+            8
+        );
+
+        assert_eq!(
+            CommandParser::parse(b"+COPS: (1,\"a)\",")
+                .expect_identifier(b"+COPS: ")
+                .repeat_groups(|parser| parser.expect_int_parameter())
+                .into_parser()
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedEnd,
+                expected: Expected::Identifier(b")"),
+                index: 7,
+            })
+        );
+
+        // A parser that is already invalid has nothing to repeat
+        let mut groups = CommandParser::parse(b"+COPS: (1)")
+            .expect_identifier(b"+CSQ: ")
+            .repeat_groups(|parser| parser.expect_int_parameter());
+        assert_eq!(groups.next(), None);
+        assert!(groups.into_parser().finish().is_err());
+    }
+
+    #[test]
+    fn test_repeat_groups() {
+        let mut groups = CommandParser::parse(b"+CSCS: (\"IRA\"), (\"a)b\"),(\"GSM\")\r\nOK\r\n")
+            .expect_identifier(b"+CSCS: ")
+            .repeat_groups(|parser| parser.expect_string_parameter());
+        assert_eq!(groups.next(), Some(Ok(("IRA",))));
+        assert_eq!(groups.next(), Some(Ok(("a)b",))));
+        assert_eq!(groups.next(), Some(Ok(("GSM",))));
+        assert_eq!(groups.next(), None);
+
+        groups
+            .into_parser()
+            .expect_identifier(b"\r\nOK\r\n")
+            .finish()
+            .unwrap();
+
+        // The rest of a group is ignored
+        let values = CommandParser::parse(b"(1,2),(3,4)")
+            .repeat_groups(|parser| parser.expect_int_parameter())
+            .map(|group| group.unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(values, [1, 3]);
+    }
+
+    #[test]
+    fn test_line_break() {
+        let (version, result) = CommandParser::parse(b"+CGMR: 1.0\n\n+CGMI: X\r\nERROR\r\n")
+            .expect_identifier(b"+CGMR:")
+            .expect_raw_string()
+            .expect_line_break()
+            .expect_identifier(b"+CGMI: X")
+            .expect_final_result()
+            .finish()
+            .unwrap();
+        assert_eq!(version, "1.0");
+        assert_eq!(result, FinalResult::Error);
+
+        assert_eq!(
+            CommandParser::parse(b"+CSQ: 20,99")
+                .expect_identifier(b"+CSQ:")
+                .expect_int_parameter()
+                .expect_line_break()
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::IdentifierMismatch,
+                expected: Expected::LineBreak,
+                index: 9,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"")
+                .expect_line_break()
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::UnexpectedEnd
+        );
+    }
+
+    #[test]
+    fn test_s_register_value() {
+        let (value, result) = CommandParser::parse(b"255\r\nOK\r\n")
+            .expect_s_register_value()
+            .expect_final_result()
+            .finish()
+            .unwrap();
+        assert_eq!(value, 255);
+        assert_eq!(result, FinalResult::Ok);
+
+        // Numeric result codes
+        let (value,) = CommandParser::parse(b"013\r\n0\r")
+            .expect_s_register_value()
+            .expect_identifier(b"\r\n0\r")
+            .finish()
+            .unwrap();
+        assert_eq!(value, 13);
+
+        assert_eq!(
+            CommandParser::parse(b"\r\n256\r\n")
+                .expect_s_register_value()
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::IntOverflow,
+                expected: Expected::IntParameter,
+                index: 2,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"\r\nERROR\r\n")
+                .expect_s_register_value()
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::InvalidInt
+        );
+    }
+
+    #[test]
+    fn test_prompt() {
+        let (result,) = CommandParser::parse(b"\r\nOK\r\n\r\n>")
+            .expect_final_result()
+            .expect_prompt()
+            .finish()
+            .unwrap();
+        assert_eq!(result, FinalResult::Ok);
+
+        assert!(CommandParser::parse(b"> ").expect_prompt().finish().is_ok());
+        assert_eq!(
+            CommandParser::parse(b"\r\nERROR\r\n")
+                .expect_prompt()
+                .finish(),
+            Err(ParseError {
+                kind: ParseErrorKind::IdentifierMismatch,
+                expected: Expected::Prompt,
+                index: 0,
+            })
+        );
+        assert_eq!(
+            CommandParser::parse(b"\r\n")
+                .expect_prompt()
+                .finish()
+                .unwrap_err()
+                .kind,
+            ParseErrorKind::UnexpectedEnd
+        );
+    }
+
+    #[test]
+    fn test_final_result() {
+        let (x, result) = CommandParser::parse(b"+SYSGPIOREAD:654\r\n\r\nOK\r\n")
+            .expect_identifier(b"+SYSGPIOREAD:")
+            .expect_int_parameter()
+            .expect_final_result()
+            .finish()
+            .unwrap();
+
+        assert_eq!(x, 654);
+        assert_eq!(result, FinalResult::Ok);
+
+        let (result,) = CommandParser::parse(b"\r\n+CMS ERROR: 321\r\n")
+            .expect_final_result()
+            .finish()
+            .unwrap();
+        assert_eq!(result, FinalResult::CmsError(321));
+
+        let (result,) = CommandParser::parse(b"NO CARRIER")
+            .expect_final_result()
+            .finish()
+            .unwrap();
+        assert_eq!(result, FinalResult::NoCarrier);
+
+        let r = CommandParser::parse(b"+SYSGPIOREAD:654\r\nOK\r\n")
+            .expect_final_result()
+            .finish();
+        assert_eq!(
+            r,
+            Err(ParseError {
+                kind: ParseErrorKind::UnknownFinalResult,
+                expected: Expected::FinalResult,
+                index: 0,
+            })
+        );
+
+        let r = CommandParser::parse(b"+SYSGPIOREAD:654\r\n")
+            .expect_identifier(b"+SYSGPIOREAD:")
+            .expect_int_parameter()
+            .expect_final_result()
+            .finish();
+        assert_eq!(r.unwrap_err().kind, ParseErrorKind::UnexpectedEnd);
+    }
+
+    #[test]
+    fn test_errors() {
+        let r = CommandParser::parse(b"+SYSGPIOREAD:654,\"true\",-65154\r\nOK\r\n")
+            .expect_identifier(b"+SYSGPIOWRITE:")
+            .expect_int_parameter()
+            .finish();
+        assert_eq!(
+            r,
+            Err(ParseError {
+                kind: ParseErrorKind::IdentifierMismatch,
+                expected: Expected::Identifier(b"+SYSGPIOWRITE:"),
+                index: 0,
+            })
+        );
+
+        let r = CommandParser::parse(b"+SYSGPIOREAD:abc\r\nOK\r\n")
+            .expect_identifier(b"+SYSGPIOREAD:")
+            .expect_int_parameter()
+            .finish();
+        assert_eq!(
+            r,
+            Err(ParseError {
+                kind: ParseErrorKind::InvalidInt,
+                expected: Expected::IntParameter,
+                index: 13,
+            })
+        );
+
+        let r = CommandParser::parse(b"+SYSGPIOREAD:654,\"tr")
+            .expect_identifier(b"+SYSGPIOREAD:")
+            .expect_int_parameter()
+            .expect_string_parameter()
+            .finish();
+        assert_eq!(
+            r,
+            Err(ParseError {
+                kind: ParseErrorKind::UnterminatedString,
+                expected: Expected::StringParameter,
+                index: 17,
+            })
+        );
+
+        let r = CommandParser::parse(b"+SYSGPIOREAD:\"\xFF\"")
+            .expect_identifier(b"+SYSGPIOREAD:")
+            .expect_string_parameter()
+            .finish();
+        assert_eq!(
+            r,
+            Err(ParseError {
+                kind: ParseErrorKind::InvalidUtf8,
+                expected: Expected::StringParameter,
+                index: 13,
+            })
+        );
+
+        let r = CommandParser::parse(b"+SYSGPIOREAD:654,")
+            .expect_identifier(b"+SYSGPIOREAD:")
+            .expect_int_parameter()
+            .expect_int_parameter()
+            .finish();
+        assert_eq!(
+            r,
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedEnd,
+                expected: Expected::IntParameter,
+                index: 17,
+            })
+        );
+
+        let r = CommandParser::parse(b"+SYSGPIO")
+            .expect_identifier(b"+SYSGPIOREAD:")
+            .finish();
+        assert_eq!(r.unwrap_err().kind, ParseErrorKind::UnexpectedEnd);
+    }
 }
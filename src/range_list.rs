@@ -0,0 +1,168 @@
+//! Module for the lists of supported values that test commands answer with
+
+use core::ops::RangeInclusive;
+
+/// A list of values and ranges of values, like the `(0,1,4)` or `(0-3)` in `+CFUN: (0,1,4),(0-1)`.
+///
+/// Test commands use these to tell which values the device supports.
+/// The list is read from the response buffer while iterating, so it doesn't need any storage.
+///
+/// ```rust
+/// use at_commands::parser::CommandParser;
+///
+/// let (functionality, reset) = CommandParser::parse(b"+CFUN: (0,1,4),(0-1)\r\nOK\r\n")
+///     .expect_identifier(b"+CFUN:")
+///     .expect_range_list_parameter()
+///     .expect_range_list_parameter()
+///     .expect_identifier(b"\r\nOK\r\n")
+///     .finish()
+///     .unwrap();
+///
+/// assert!(functionality.contains(4));
+/// assert!(!functionality.contains(2));
+/// assert_eq!(reset.iter().collect::<Vec<_>>(), [0..=1]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RangeList<'a> {
+    /// The items between the parentheses
+    items: &'a [u8],
+}
+
+impl<'a> RangeList<'a> {
+    /// Creates the list from the items between the parentheses, like `0,2-5`.
+    ///
+    /// Returns None if an item is not a value or a range of values.
+    pub(crate) fn new(items: &'a [u8]) -> Option<Self> {
+        let mut remaining = items;
+
+        while !remaining.is_empty() {
+            remaining = next_range(remaining)?.1;
+        }
+
+        Some(Self { items })
+    }
+
+    /// An iterator over the ranges in the list. A single value is a range with the same start and end.
+    pub fn iter(&self) -> RangeListIter<'a> {
+        RangeListIter {
+            remaining: self.items,
+        }
+    }
+
+    /// Returns true if the value is in one of the ranges of the list
+    pub fn contains(&self, value: i32) -> bool {
+        self.iter().any(|range| range.contains(&value))
+    }
+
+    /// Returns true if the list has no values
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The text between the parentheses as it was received
+    pub fn raw(&self) -> &'a [u8] {
+        self.items
+    }
+}
+
+impl<'a> IntoIterator for RangeList<'a> {
+    type Item = RangeInclusive<i32>;
+    type IntoIter = RangeListIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the ranges of a [RangeList]
+#[derive(Debug, Clone)]
+pub struct RangeListIter<'a> {
+    remaining: &'a [u8],
+}
+
+impl Iterator for RangeListIter<'_> {
+    type Item = RangeInclusive<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The list was checked when it was created, so a parse failure can only be the end
+        let (range, remaining) = next_range(self.remaining)?;
+        self.remaining = remaining;
+        Some(range)
+    }
+}
+
+/// Reads the range at the start of the items and gives back the items after it
+fn next_range(items: &[u8]) -> Option<(RangeInclusive<i32>, &[u8])> {
+    let (start, rest) = next_value(items)?;
+
+    let (end, rest) = match rest {
+        [b'-', rest @ ..] => next_value(rest)?,
+        _ => (start, rest),
+    };
+
+    match rest {
+        [] => Some((start..=end, rest)),
+        // A trailing comma would be an empty item
+        [b',', rest @ ..] if !rest.is_empty() => Some((start..=end, rest)),
+        _ => None,
+    }
+}
+
+/// Reads the (possibly negative) value at the start of the items
+fn next_value(items: &[u8]) -> Option<(i32, &[u8])> {
+    let sign_length = (items.first() == Some(&b'-')) as usize;
+    let length = sign_length
+        + items[sign_length..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+
+    if length == sign_length {
+        return None;
+    }
+
+    crate::formatter::parse_int(&items[..length])
+        .ok()
+        .map(|value| (value, &items[length..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(items: &[u8]) -> Vec<RangeInclusive<i32>> {
+        RangeList::new(items).unwrap().iter().collect()
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(ranges(b"0,1,4"), [0..=0, 1..=1, 4..=4]);
+        assert_eq!(ranges(b"0-3"), [0..=3]);
+        assert_eq!(ranges(b"0-31,99"), [0..=31, 99..=99]);
+        assert_eq!(ranges(b"-10--5,-1-1"), [-10..=-5, -1..=1]);
+        assert_eq!(ranges(b""), []);
+
+        let list = RangeList::new(b"1-3,7").unwrap();
+        assert!(list.contains(1) && list.contains(3) && list.contains(7));
+        assert!(!list.contains(0) && !list.contains(4));
+        assert!(!RangeList::new(b"").unwrap().contains(0));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for items in [
+            &b"1,"[..],
+            b",1",
+            b"1,,2",
+            b"1-",
+            b"-",
+            b"1-2-3",
+            b"a",
+            b"1 ,2",
+            b"99999999999",
+        ] {
+            assert_eq!(RangeList::new(items), None, "{:?}", items);
+        }
+    }
+}
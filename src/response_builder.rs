@@ -0,0 +1,517 @@
+//! Implementation of the ResponseBuilder, for implementing the device side (DCE)
+
+use crate::escape::Escaping;
+use crate::result_code::{CmeError, FinalResult};
+use crate::Integer;
+
+/// # ResponseBuilder
+/// A builder struct for the responses to AT Commands
+///
+/// ## Summary
+/// This is the device side counterpart of the [CommandBuilder](crate::builder::CommandBuilder).
+/// It can be used to build:
+/// * Information responses in the form `{name}: {param},{param},{param}`
+/// * Lines of free information text
+/// * The final result code, like `OK` or `+CME ERROR: {err}`
+///
+/// The framing of the lines and the final result code follows the [ResultCodeFormat],
+/// which is what a device sets with `ATV` and `ATQ`.
+///
+/// ## Example
+/// ```rust
+/// use at_commands::response_builder::{ResponseBuilder, ResultCodeFormat};
+///
+/// let mut buffer = [0; 128];
+///
+/// let result = ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+///     .information("+NAME")
+///     .with_int_parameter(1)
+///     .with_string_parameter("x")
+///     .finish_ok()
+///     .unwrap();
+///
+/// assert_eq!(result, b"\r\n+NAME: 1,\"x\"\r\n\r\nOK\r\n");
+/// ```
+pub struct ResponseBuilder<'a, STAGE> {
+    buffer: &'a mut [u8],
+    index: usize,
+    format: ResultCodeFormat,
+    has_information: bool,
+    phantom: core::marker::PhantomData<STAGE>,
+}
+
+/// How the device frames its responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResultCodeFormat {
+    /// Verbose (`ATV1`) or numeric (`ATV0`) result codes.
+    ///
+    /// In verbose mode, information text is framed as `\r\n{text}\r\n` and result codes as `\r\n{code}\r\n`.
+    /// In numeric mode, information text is framed as `{text}\r\n` and result codes as `{number}\r`.
+    pub verbose: bool,
+    /// Quiet mode (`ATQ1`) where no result codes are sent at all
+    pub quiet: bool,
+}
+
+impl Default for ResultCodeFormat {
+    /// The default of V.250: verbose and not quiet
+    fn default() -> Self {
+        Self {
+            verbose: true,
+            quiet: false,
+        }
+    }
+}
+
+impl<'a> ResponseBuilder<'a, Lines> {
+    /// Creates a builder for a response.
+    ///
+    /// The given buffer is used to build the response in and must be big enough to contain it.
+    pub fn create(buffer: &'a mut [u8], format: ResultCodeFormat) -> Self {
+        Self {
+            buffer,
+            index: 0,
+            format,
+            has_information: false,
+            phantom: Default::default(),
+        }
+    }
+}
+
+impl<'a, STAGE: ResponseStage> ResponseBuilder<'a, STAGE> {
+    /// Tries to append data to the buffer.
+    ///
+    /// If it won't fit, it silently fails and won't copy the data.
+    /// The index field is incremented no matter what.
+    fn try_append_data(&mut self, data: &[u8]) {
+        self.index = crate::formatter::try_append_data(self.buffer, self.index, data);
+    }
+
+    fn try_append_int<INT: Integer>(&mut self, value: INT) {
+        let mut formatting_buffer = [0; crate::formatter::MAX_INT_DIGITS];
+        self.try_append_data(crate::formatter::write_int(&mut formatting_buffer, value));
+    }
+
+    /// Ends the information line that is being built, if any
+    fn end_line(&mut self) {
+        if STAGE::LINE_OPEN {
+            // if last byte is a comma, decrement index to drop it
+            if let Some(b',') = self.buffer.get(self.index.wrapping_sub(1)) {
+                self.index -= 1;
+            }
+            self.try_append_data(b"\r\n");
+        }
+    }
+
+    /// Starts a new information line
+    fn start_line(&mut self) {
+        // In verbose mode, the block of information text is preceded by a line break
+        if self.format.verbose && !self.has_information {
+            self.try_append_data(b"\r\n");
+        }
+        self.has_information = true;
+    }
+
+    fn into_stage<NEXT>(self) -> ResponseBuilder<'a, NEXT> {
+        ResponseBuilder {
+            buffer: self.buffer,
+            index: self.index,
+            format: self.format,
+            has_information: self.has_information,
+            phantom: Default::default(),
+        }
+    }
+
+    /// Starts an information response line in the form `{name}: {param},{param}`.
+    ///
+    /// The parameters can be added to the returned builder.
+    pub fn information<T: AsRef<[u8]>>(mut self, name: T) -> ResponseBuilder<'a, Information> {
+        self.end_line();
+        self.start_line();
+        self.try_append_data(name.as_ref());
+        self.try_append_data(b": ");
+        self.into_stage()
+    }
+
+    /// Adds a line of free information text.
+    pub fn with_text_line<T: AsRef<[u8]>>(mut self, text: T) -> ResponseBuilder<'a, Lines> {
+        self.end_line();
+        self.start_line();
+        self.try_append_data(text.as_ref());
+        self.try_append_data(b"\r\n");
+        self.into_stage()
+    }
+
+    /// Finishes the builder with an `OK` result code.
+    ///
+    /// See [Self::finish] for the return value.
+    pub fn finish_ok(self) -> Result<&'a [u8], usize> {
+        self.finish(FinalResult::Ok)
+    }
+
+    /// Finishes the builder with the given final result code.
+    ///
+    /// When Ok, it returns a slice with the built response.
+    /// The slice points to the same memory as the buffer,
+    /// but is only as long as is required to contain the response.
+    ///
+    /// If the buffer was not long enough,
+    /// then an Err is returned with the size that was required for it to succeed.
+    pub fn finish(mut self, result: FinalResult) -> Result<&'a [u8], usize> {
+        self.end_line();
+
+        if !self.format.quiet {
+            if self.format.verbose {
+                self.try_append_data(b"\r\n");
+                self.append_verbose_result(result);
+                self.try_append_data(b"\r\n");
+            } else {
+                self.append_numeric_result(result);
+                self.try_append_data(b"\r");
+            }
+        }
+
+        self.finish_buffer()
+    }
+
+    /// Finishes the builder without a final result code.
+    ///
+    /// This can be used for unsolicited result codes or intermediate responses.
+    /// See [Self::finish] for the return value.
+    pub fn finish_without_result(mut self) -> Result<&'a [u8], usize> {
+        self.end_line();
+        self.finish_buffer()
+    }
+
+    fn finish_buffer(self) -> Result<&'a [u8], usize> {
+        if self.index > self.buffer.len() {
+            Err(self.index)
+        } else {
+            Ok(&self.buffer[0..self.index])
+        }
+    }
+
+    fn append_verbose_result(&mut self, result: FinalResult) {
+        match result {
+            FinalResult::Ok => self.try_append_data(b"OK"),
+            FinalResult::Connect(None) => self.try_append_data(b"CONNECT"),
+            FinalResult::Connect(Some(text)) => {
+                self.try_append_data(b"CONNECT ");
+                self.try_append_data(text.as_bytes());
+            }
+            FinalResult::Error => self.try_append_data(b"ERROR"),
+            FinalResult::NoCarrier => self.try_append_data(b"NO CARRIER"),
+            FinalResult::Busy => self.try_append_data(b"BUSY"),
+            FinalResult::NoAnswer => self.try_append_data(b"NO ANSWER"),
+            FinalResult::NoDialtone => self.try_append_data(b"NO DIALTONE"),
+            extended => self.append_extended_error(extended),
+        }
+    }
+
+    fn append_numeric_result(&mut self, result: FinalResult) {
+        // The numeric codes as defined in V.250
+        match result {
+            FinalResult::Ok => self.try_append_data(b"0"),
+            FinalResult::Connect(_) => self.try_append_data(b"1"),
+            FinalResult::NoCarrier => self.try_append_data(b"3"),
+            FinalResult::Error => self.try_append_data(b"4"),
+            FinalResult::NoDialtone => self.try_append_data(b"6"),
+            FinalResult::Busy => self.try_append_data(b"7"),
+            FinalResult::NoAnswer => self.try_append_data(b"8"),
+            // The extended errors don't have a number, so they're always verbose
+            extended => self.append_extended_error(extended),
+        }
+    }
+
+    fn append_extended_error(&mut self, result: FinalResult) {
+        match result {
+            FinalResult::CmeError(CmeError::Code(code)) => {
+                self.try_append_data(b"+CME ERROR: ");
+                self.try_append_int(code);
+            }
+            FinalResult::CmeError(CmeError::Text(text)) => {
+                self.try_append_data(b"+CME ERROR: ");
+                self.try_append_data(text.as_bytes());
+            }
+            FinalResult::CmsError(code) => {
+                self.try_append_data(b"+CMS ERROR: ");
+                self.try_append_int(code);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a> ResponseBuilder<'a, Information> {
+    /// Add an integer parameter.
+    pub fn with_int_parameter<INT: Into<i32>>(self, value: INT) -> Self {
+        self.with_int_parameter_as(value.into())
+    }
+
+    /// Add an integer parameter of any [Integer] type, like a `u32` or `i64`.
+    pub fn with_int_parameter_as<INT: Integer>(mut self, value: INT) -> Self {
+        self.try_append_int(value);
+        self.try_append_data(b",");
+        self
+    }
+
+    /// Add a string parameter
+    pub fn with_string_parameter<T: AsRef<[u8]>>(self, value: T) -> Self {
+        self.with_escaped_string_parameter(value, Escaping::None)
+    }
+
+    /// Add a string parameter in which the special characters are escaped.
+    ///
+    /// See [Escaping] for which characters get escaped and how.
+    pub fn with_escaped_string_parameter<T: AsRef<[u8]>>(
+        mut self,
+        value: T,
+        escaping: Escaping,
+    ) -> Self {
+        self.try_append_data(b"\"");
+        escaping.escape(value.as_ref(), |data| self.try_append_data(data));
+        self.try_append_data(b"\"");
+        self.try_append_data(b",");
+        self
+    }
+
+    /// Add an optional integer parameter.
+    pub fn with_optional_int_parameter<INT: Into<i32>>(self, value: Option<INT>) -> Self {
+        self.with_optional_int_parameter_as(value.map(Into::into))
+    }
+
+    /// Add an optional integer parameter of any [Integer] type.
+    pub fn with_optional_int_parameter_as<INT: Integer>(self, value: Option<INT>) -> Self {
+        match value {
+            None => self.with_empty_parameter(),
+            Some(value) => self.with_int_parameter_as(value),
+        }
+    }
+
+    /// Add an optional string parameter.
+    pub fn with_optional_string_parameter<T: AsRef<[u8]>>(self, value: Option<T>) -> Self {
+        match value {
+            None => self.with_empty_parameter(),
+            Some(value) => self.with_string_parameter(value),
+        }
+    }
+
+    /// Add a comma, representing an unset optional parameter.
+    pub fn with_empty_parameter(mut self) -> Self {
+        self.try_append_data(b",");
+        self
+    }
+
+    /// Add an unformatted parameter
+    pub fn with_raw_parameter<T: AsRef<[u8]>>(mut self, value: T) -> Self {
+        self.try_append_data(value.as_ref());
+        self.try_append_data(b",");
+        self
+    }
+}
+
+/// Marker struct for response builders that have no information line open.
+pub struct Lines;
+/// Marker struct for response builders that are building an information line.
+pub struct Information;
+
+/// A trait that is implemented for the marker structs of the response builder.
+pub trait ResponseStage {
+    /// True if an information line is being built that must be ended first.
+    const LINE_OPEN: bool;
+}
+impl ResponseStage for Lines {
+    const LINE_OPEN: bool = false;
+}
+impl ResponseStage for Information {
+    const LINE_OPEN: bool = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NUMERIC: ResultCodeFormat = ResultCodeFormat {
+        verbose: false,
+        quiet: false,
+    };
+    const QUIET: ResultCodeFormat = ResultCodeFormat {
+        verbose: true,
+        quiet: true,
+    };
+
+    #[test]
+    fn test_ok() {
+        let mut buffer = [0; 128];
+
+        let value = ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+            .finish_ok()
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "\r\nOK\r\n");
+
+        let value = ResponseBuilder::create(&mut buffer, NUMERIC)
+            .finish_ok()
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "0\r");
+
+        let value = ResponseBuilder::create(&mut buffer, QUIET)
+            .finish_ok()
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "");
+    }
+
+    #[test]
+    fn test_multi_line() {
+        let mut buffer = [0; 128];
+
+        let value = ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+            .information("+CGDCONT")
+            .with_int_parameter(1)
+            .with_string_parameter("IP")
+            .with_optional_string_parameter(Some("internet"))
+            .with_escaped_string_parameter("a,\"b\"", Escaping::Backslash)
+            .information("+CGDCONT")
+            .with_int_parameter(2)
+            .with_raw_parameter("IPV6")
+            .with_empty_parameter()
+            .with_optional_int_parameter(Some(0))
+            .finish_ok()
+            .unwrap();
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "\r\n+CGDCONT: 1,\"IP\",\"internet\",\"a\\,\\\"b\\\"\"\r\n+CGDCONT: 2,IPV6,,0\r\n\r\nOK\r\n"
+        );
+
+        let value = ResponseBuilder::create(&mut buffer, NUMERIC)
+            .with_text_line("Manufacturer")
+            .with_text_line("Model")
+            .finish_ok()
+            .unwrap();
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "Manufacturer\r\nModel\r\n0\r"
+        );
+
+        let value = ResponseBuilder::create(&mut buffer, QUIET)
+            .information("+CSQ")
+            .with_int_parameter(20)
+            .with_int_parameter(99)
+            .finish_ok()
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "\r\n+CSQ: 20,99\r\n");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut buffer = [0; 128];
+
+        let value = ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+            .finish(FinalResult::CmeError(CmeError::Code(10)))
+            .unwrap();
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "\r\n+CME ERROR: 10\r\n"
+        );
+
+        let value = ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+            .finish(FinalResult::CmeError(CmeError::Text("SIM not inserted")))
+            .unwrap();
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "\r\n+CME ERROR: SIM not inserted\r\n"
+        );
+
+        let value = ResponseBuilder::create(&mut buffer, NUMERIC)
+            .finish(FinalResult::CmsError(500))
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "+CMS ERROR: 500\r");
+
+        let value = ResponseBuilder::create(&mut buffer, NUMERIC)
+            .finish(FinalResult::Error)
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "4\r");
+
+        let value = ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+            .finish(FinalResult::Connect(Some("115200")))
+            .unwrap();
+        assert_eq!(
+            core::str::from_utf8(value).unwrap(),
+            "\r\nCONNECT 115200\r\n"
+        );
+    }
+
+    #[test]
+    fn test_unsolicited() {
+        let mut buffer = [0; 128];
+
+        let value = ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+            .information("+CREG")
+            .with_int_parameter(1)
+            .finish_without_result()
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "\r\n+CREG: 1\r\n");
+    }
+
+    #[test]
+    fn test_buffer_too_short() {
+        let mut buffer = [0; 16];
+
+        assert_eq!(
+            ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+                .information("+CSQ")
+                .with_int_parameter(20)
+                .with_int_parameter(99)
+                .finish_ok(),
+            Err(21)
+        );
+
+        let value = ResponseBuilder::create(&mut buffer[..6], ResultCodeFormat::default())
+            .finish_ok()
+            .unwrap();
+        assert_eq!(core::str::from_utf8(value).unwrap(), "\r\nOK\r\n");
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut buffer = [0; 128];
+
+        let value = ResponseBuilder::create(&mut buffer, ResultCodeFormat::default())
+            .information("+SYSGPIOREAD")
+            .with_int_parameter(654)
+            .with_string_parameter("true")
+            .with_int_parameter(-65154)
+            .finish(FinalResult::CmeError(CmeError::Code(3)))
+            .unwrap();
+
+        let (x, y, z, result) = crate::parser::CommandParser::parse(value)
+            .expect_identifier(b"\r\n+SYSGPIOREAD:")
+            .expect_int_parameter()
+            .expect_string_parameter()
+            .expect_int_parameter()
+            .expect_final_result()
+            .finish()
+            .unwrap();
+
+        assert_eq!((x, y, z), (654, "true", -65154));
+        assert_eq!(result, FinalResult::CmeError(CmeError::Code(3)));
+
+        // The numeric result codes are read back as well
+        let value = ResponseBuilder::create(&mut buffer, NUMERIC)
+            .information("+CSQ")
+            .with_int_parameter(20)
+            .finish(FinalResult::Error)
+            .unwrap();
+
+        let mut digester = crate::digester::Digester::<32>::new();
+        digester.set_result_code_format(NUMERIC);
+        digester.feed(value);
+        assert_eq!(
+            digester.next_item(),
+            Some(crate::digester::DigestItem::Line(b"+CSQ: 20"))
+        );
+        assert_eq!(
+            digester.next_item(),
+            Some(crate::digester::DigestItem::FinalResult(FinalResult::Error))
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Module that defines the final result codes that end a response
+
+use crate::response_builder::ResultCodeFormat;
+
+/// # FinalResult
+/// The final result code a device ends its response with
+///
+/// These are the result codes of V.250 and the extended error codes of 3GPP 27.007 and 27.005.
+///
+/// ## Example
+/// ```rust
+/// use at_commands::result_code::{CmeError, FinalResult};
+///
+/// assert_eq!(FinalResult::parse_line(b"OK"), Some(FinalResult::Ok));
+/// assert_eq!(
+///     FinalResult::parse_line(b"+CME ERROR: 10"),
+///     Some(FinalResult::CmeError(CmeError::Code(10)))
+/// );
+/// assert_eq!(FinalResult::parse_line(b"+CSQ: 20,99"), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FinalResult<'a> {
+    /// `OK`
+    Ok,
+    /// `CONNECT`, optionally followed by some text like the connection speed
+    Connect(Option<&'a str>),
+    /// `ERROR`
+    Error,
+    /// `NO CARRIER`
+    NoCarrier,
+    /// `BUSY`
+    Busy,
+    /// `NO ANSWER`
+    NoAnswer,
+    /// `NO DIALTONE`
+    NoDialtone,
+    /// `+CME ERROR: <err>`, an error of the mobile equipment
+    CmeError(CmeError<'a>),
+    /// `+CMS ERROR: <err>`, an error of the message service
+    CmsError(i32),
+}
+
+/// The error reported with `+CME ERROR`.
+///
+/// Depending on the `+CMEE` setting, the device reports a numeric code or a verbose text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CmeError<'a> {
+    /// The numeric error code
+    Code(i32),
+    /// The verbose error text
+    Text(&'a str),
+}
+
+impl<'a> FinalResult<'a> {
+    /// Parses a single line into a final result code.
+    ///
+    /// The line must not contain the line terminator.
+    /// Returns None if the line is not a final result code.
+    ///
+    /// Only the verbose codes of the default `ATV1` mode are recognized.
+    /// Use [Self::parse_line_with] for a device in the numeric mode of `ATV0`.
+    pub fn parse_line(line: &'a [u8]) -> Option<Self> {
+        Self::parse_line_with(line, ResultCodeFormat::default())
+    }
+
+    /// Parses a single line into a final result code of the given format.
+    ///
+    /// In verbose mode this is the same as [Self::parse_line].
+    /// In numeric mode, the numeric codes of V.250 are recognized instead of the verbose ones.
+    /// The numeric code `2` is `RING`, which is an unsolicited result code and not a final one.
+    /// The extended errors like `+CME ERROR` don't have a number, so they're recognized in both modes.
+    ///
+    /// A line of information text that is just a number can't be told apart from a numeric code,
+    /// so don't use the numeric mode unless the device is set to it.
+    ///
+    /// ```rust
+    /// use at_commands::response_builder::ResultCodeFormat;
+    /// use at_commands::result_code::FinalResult;
+    ///
+    /// let numeric = ResultCodeFormat {
+    ///     verbose: false,
+    ///     quiet: false,
+    /// };
+    ///
+    /// assert_eq!(FinalResult::parse_line_with(b"4", numeric), Some(FinalResult::Error));
+    /// assert_eq!(FinalResult::parse_line_with(b"ERROR", numeric), None);
+    /// assert_eq!(FinalResult::parse_line(b"4"), None);
+    /// ```
+    pub fn parse_line_with(line: &'a [u8], format: ResultCodeFormat) -> Option<Self> {
+        const CONNECT: &[u8] = b"CONNECT";
+
+        if !format.verbose {
+            // The numeric codes as defined in V.250
+            return match line {
+                b"0" => Some(FinalResult::Ok),
+                b"1" => Some(FinalResult::Connect(None)),
+                b"3" => Some(FinalResult::NoCarrier),
+                b"4" => Some(FinalResult::Error),
+                b"6" => Some(FinalResult::NoDialtone),
+                b"7" => Some(FinalResult::Busy),
+                b"8" => Some(FinalResult::NoAnswer),
+                _ => Self::parse_extended_error(line),
+            };
+        }
+
+        match line {
+            b"OK" => Some(FinalResult::Ok),
+            b"ERROR" => Some(FinalResult::Error),
+            b"NO CARRIER" => Some(FinalResult::NoCarrier),
+            b"BUSY" => Some(FinalResult::Busy),
+            b"NO ANSWER" => Some(FinalResult::NoAnswer),
+            b"NO DIALTONE" => Some(FinalResult::NoDialtone),
+            b"CONNECT" => Some(FinalResult::Connect(None)),
+            _ if line.starts_with(CONNECT) && line.get(CONNECT.len()) == Some(&b' ') => {
+                let text = trim_leading_space(&line[CONNECT.len()..]);
+                core::str::from_utf8(text)
+                    .ok()
+                    .map(|text| FinalResult::Connect(Some(text)))
+            }
+            _ => Self::parse_extended_error(line),
+        }
+    }
+
+    fn parse_extended_error(line: &'a [u8]) -> Option<Self> {
+        const CME_ERROR: &[u8] = b"+CME ERROR:";
+        const CMS_ERROR: &[u8] = b"+CMS ERROR:";
+
+        if line.starts_with(CME_ERROR) {
+            let value = trim_leading_space(&line[CME_ERROR.len()..]);
+            match crate::formatter::parse_int(value) {
+                Ok(code) => Some(FinalResult::CmeError(CmeError::Code(code))),
+                Err(_) => core::str::from_utf8(value)
+                    .ok()
+                    .map(|text| FinalResult::CmeError(CmeError::Text(text))),
+            }
+        } else if line.starts_with(CMS_ERROR) {
+            let value = trim_leading_space(&line[CMS_ERROR.len()..]);
+            crate::formatter::parse_int(value)
+                .ok()
+                .map(FinalResult::CmsError)
+        } else {
+            None
+        }
+    }
+
+    /// Splits a complete response into the information text and the final result code it ends with.
+    ///
+    /// The final result code must be on the last line of the response.
+    /// The information text is returned as is, including its line terminators.
+    ///
+    /// ```rust
+    /// use at_commands::result_code::{CmeError, FinalResult};
+    ///
+    /// let (information, result) = FinalResult::split_response(b"+CSQ: 20,99\r\n\r\nOK\r\n").unwrap();
+    /// assert_eq!(information, b"+CSQ: 20,99\r\n\r\n");
+    /// assert_eq!(result, FinalResult::Ok);
+    ///
+    /// let (information, result) = FinalResult::split_response(b"\r\n+CME ERROR: 10\r\n").unwrap();
+    /// assert_eq!(information, b"\r\n");
+    /// assert_eq!(result, FinalResult::CmeError(CmeError::Code(10)));
+    /// ```
+    pub fn split_response(response: &'a [u8]) -> Option<(&'a [u8], Self)> {
+        // Strip the line terminator(s) at the end
+        let end = response.len()
+            - response
+                .iter()
+                .rev()
+                .take_while(|byte| is_line_terminator(**byte))
+                .count();
+        // Find the start of the last line
+        let start = response[..end]
+            .iter()
+            .rposition(|byte| is_line_terminator(*byte))
+            .map(|index| index + 1)
+            .unwrap_or(0);
+
+        Self::parse_line(&response[start..end]).map(|result| (&response[..start], result))
+    }
+
+    /// Returns true if the result code reports a failure.
+    ///
+    /// Only `OK` and `CONNECT` are successful.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, FinalResult::Ok | FinalResult::Connect(_))
+    }
+}
+
+/// Returns true for the characters that end a line
+pub(crate) fn is_line_terminator(byte: u8) -> bool {
+    byte == b'\r' || byte == b'\n'
+}
+
+fn trim_leading_space(mut value: &[u8]) -> &[u8] {
+    while let [b' ', rest @ ..] = value {
+        value = rest;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(FinalResult::parse_line(b"OK"), Some(FinalResult::Ok));
+        assert_eq!(FinalResult::parse_line(b"ERROR"), Some(FinalResult::Error));
+        assert_eq!(
+            FinalResult::parse_line(b"NO CARRIER"),
+            Some(FinalResult::NoCarrier)
+        );
+        assert_eq!(FinalResult::parse_line(b"BUSY"), Some(FinalResult::Busy));
+        assert_eq!(
+            FinalResult::parse_line(b"NO ANSWER"),
+            Some(FinalResult::NoAnswer)
+        );
+        assert_eq!(
+            FinalResult::parse_line(b"NO DIALTONE"),
+            Some(FinalResult::NoDialtone)
+        );
+        assert_eq!(
+            FinalResult::parse_line(b"CONNECT"),
+            Some(FinalResult::Connect(None))
+        );
+        assert_eq!(
+            FinalResult::parse_line(b"CONNECT 115200"),
+            Some(FinalResult::Connect(Some("115200")))
+        );
+        assert_eq!(
+            FinalResult::parse_line(b"+CME ERROR: 10"),
+            Some(FinalResult::CmeError(CmeError::Code(10)))
+        );
+        assert_eq!(
+            FinalResult::parse_line(b"+CME ERROR:3"),
+            Some(FinalResult::CmeError(CmeError::Code(3)))
+        );
+        assert_eq!(
+            FinalResult::parse_line(b"+CME ERROR: SIM not inserted"),
+            Some(FinalResult::CmeError(CmeError::Text("SIM not inserted")))
+        );
+        assert_eq!(
+            FinalResult::parse_line(b"+CMS ERROR: 500"),
+            Some(FinalResult::CmsError(500))
+        );
+
+        assert_eq!(FinalResult::parse_line(b""), None);
+        assert_eq!(FinalResult::parse_line(b"0"), None);
+        assert_eq!(FinalResult::parse_line(b"4"), None);
+        assert_eq!(FinalResult::parse_line(b"OK "), None);
+        assert_eq!(FinalResult::parse_line(b"CONNECTED"), None);
+        assert_eq!(FinalResult::parse_line(b"+CMS ERROR: unknown"), None);
+        assert_eq!(FinalResult::parse_line(b"+CSQ: 20,99"), None);
+    }
+
+    #[test]
+    fn test_parse_line_numeric() {
+        let numeric = ResultCodeFormat {
+            verbose: false,
+            quiet: false,
+        };
+        let parse = |line| FinalResult::parse_line_with(line, numeric);
+
+        assert_eq!(parse(b"0"), Some(FinalResult::Ok));
+        assert_eq!(parse(b"1"), Some(FinalResult::Connect(None)));
+        assert_eq!(parse(b"3"), Some(FinalResult::NoCarrier));
+        assert_eq!(parse(b"4"), Some(FinalResult::Error));
+        assert_eq!(parse(b"6"), Some(FinalResult::NoDialtone));
+        assert_eq!(parse(b"7"), Some(FinalResult::Busy));
+        assert_eq!(parse(b"8"), Some(FinalResult::NoAnswer));
+        assert_eq!(
+            parse(b"+CME ERROR: 10"),
+            Some(FinalResult::CmeError(CmeError::Code(10)))
+        );
+        assert_eq!(parse(b"+CMS ERROR: 500"), Some(FinalResult::CmsError(500)));
+
+        assert_eq!(parse(b"2"), None);
+        assert_eq!(parse(b"5"), None);
+        assert_eq!(parse(b"00"), None);
+        assert_eq!(parse(b"OK"), None);
+        assert_eq!(parse(b"CONNECT 115200"), None);
+    }
+
+    #[test]
+    fn test_split_response() {
+        assert_eq!(
+            FinalResult::split_response(b"OK"),
+            Some((&b""[..], FinalResult::Ok))
+        );
+        assert_eq!(
+            FinalResult::split_response(b"\r\n+CGMR: 1.0\r\n\r\nERROR\r\n"),
+            Some((&b"\r\n+CGMR: 1.0\r\n\r\n"[..], FinalResult::Error))
+        );
+        assert_eq!(FinalResult::split_response(b"+CSQ: 20,99\r\n"), None);
+        assert_eq!(FinalResult::split_response(b""), None);
+    }
+
+    #[test]
+    fn test_is_error() {
+        assert!(!FinalResult::Ok.is_error());
+        assert!(!FinalResult::Connect(None).is_error());
+        assert!(FinalResult::Error.is_error());
+        assert!(FinalResult::CmsError(500).is_error());
+    }
+}
@@ -0,0 +1,745 @@
+//! Module for recording the traffic with a device as a transcript and replaying it in tests
+//!
+//! A transcript is a text file with an entry on every line:
+//!
+//! ```text
+//! # Comments start with a '#'
+//! 0 TX "AT+CSQ\r\n"
+//! 12 RX "AT+CSQ\r\r\n+CSQ: 20,99\r\n"
+//! 13 RX "\r\nOK\r\n"
+//! ```
+//!
+//! Every entry has the time in milliseconds since the start of the transcript,
+//! the direction (`TX` for what the host sent and `RX` for what the device sent) and the data as a quoted string.
+//! In the data, `\r`, `\n`, `\t`, `\"` and `\\` are escaped,
+//! and all other bytes that aren't printable ASCII are written as `\xHH`.
+
+use std::fmt;
+use std::io;
+use std::time::Instant;
+use std::vec::Vec;
+
+/// The direction of an [Entry]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent by the host, written as `TX`
+    Tx,
+    /// Sent by the device, written as `RX`
+    Rx,
+}
+
+/// A piece of data that went over the line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The time in milliseconds since the start of the transcript
+    pub time_ms: u64,
+    /// Who sent the data
+    pub direction: Direction,
+    /// The bytes that were sent
+    pub data: Vec<u8>,
+}
+
+/// # Transcript
+/// The data that went back and forth between the host and a device
+///
+/// ## Example
+/// ```rust
+/// use at_commands::transcript::{Direction, Transcript};
+///
+/// let transcript = Transcript::parse(
+///     "# Signal quality\n\
+///      0 TX \"AT+CSQ\\r\\n\"\n\
+///      12 RX \"\\r\\n+CSQ: 20,99\\r\\n\\r\\nOK\\r\\n\"\n",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(transcript.entries.len(), 2);
+/// assert_eq!(transcript.entries[0].direction, Direction::Tx);
+/// assert_eq!(transcript.entries[0].data, b"AT+CSQ\r\n");
+/// assert_eq!(transcript.entries[1].time_ms, 12);
+///
+/// // It's written back in the same format
+/// assert_eq!(
+///     transcript.to_string(),
+///     "0 TX \"AT+CSQ\\r\\n\"\n12 RX \"\\r\\n+CSQ: 20,99\\r\\n\\r\\nOK\\r\\n\"\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript {
+    /// The entries in the order they happened
+    pub entries: Vec<Entry>,
+}
+
+/// The error for a line of a transcript that can't be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TranscriptError {
+    /// The number of the line, starting at 1
+    pub line: usize,
+    /// What is wrong with the line
+    pub kind: TranscriptErrorKind,
+}
+
+/// What is wrong with a line of a transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptErrorKind {
+    /// The line doesn't start with the time in milliseconds
+    InvalidTime,
+    /// The direction is not `TX` or `RX`
+    InvalidDirection,
+    /// The data is not a quoted string or there's something after it
+    InvalidData,
+    /// The data has an escape sequence that is not valid
+    InvalidEscape,
+}
+
+impl Transcript {
+    /// Creates an empty transcript
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the text of a transcript
+    pub fn parse(text: &str) -> Result<Self, TranscriptError> {
+        let mut transcript = Self::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let entry = parse_entry(line).map_err(|kind| TranscriptError {
+                line: index + 1,
+                kind,
+            })?;
+            transcript.entries.push(entry);
+        }
+
+        Ok(transcript)
+    }
+
+    /// Adds an entry at the end
+    pub fn push(&mut self, time_ms: u64, direction: Direction, data: &[u8]) {
+        self.entries.push(Entry {
+            time_ms,
+            direction,
+            data: data.to_vec(),
+        });
+    }
+
+    /// All data in the given direction after each other
+    pub fn data(&self, direction: Direction) -> Vec<u8> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.direction == direction)
+            .flat_map(|entry| entry.data.iter().copied())
+            .collect()
+    }
+
+    /// Turns the transcript into a script for a [MockModem](crate::mock::MockModem).
+    ///
+    /// What the host sent is expected, what the device sent is the response
+    /// and the time between the entries becomes a delay before every response.
+    #[cfg(feature = "mock")]
+    pub fn steps(&self) -> Vec<crate::mock::Step<'_>> {
+        use crate::mock::Step;
+
+        let mut steps = Vec::new();
+        let mut previous_time_ms = 0;
+
+        for entry in &self.entries {
+            match entry.direction {
+                Direction::Tx => steps.push(Step::Expect(&entry.data)),
+                Direction::Rx => {
+                    let delay = entry.time_ms.saturating_sub(previous_time_ms);
+                    if delay > 0 {
+                        steps.push(Step::Delay(delay.try_into().unwrap_or(u32::MAX)));
+                    }
+                    steps.push(Step::Respond(&entry.data));
+                }
+            }
+            previous_time_ms = entry.time_ms;
+        }
+
+        steps
+    }
+}
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let direction = match entry.direction {
+                Direction::Tx => "TX",
+                Direction::Rx => "RX",
+            };
+            writeln!(
+                f,
+                "{} {} \"{}\"",
+                entry.time_ms,
+                direction,
+                Escaped(&entry.data)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            TranscriptErrorKind::InvalidTime => "expected the time in milliseconds",
+            TranscriptErrorKind::InvalidDirection => "expected TX or RX",
+            TranscriptErrorKind::InvalidData => "expected the data as a quoted string",
+            TranscriptErrorKind::InvalidEscape => "invalid escape sequence in the data",
+        };
+        write!(f, "line {}: {}", self.line, reason)
+    }
+}
+
+impl std::error::Error for TranscriptError {}
+
+fn parse_entry(line: &str) -> Result<Entry, TranscriptErrorKind> {
+    let (time, rest) = line
+        .split_once(' ')
+        .ok_or(TranscriptErrorKind::InvalidTime)?;
+    let time_ms = time.parse().map_err(|_| TranscriptErrorKind::InvalidTime)?;
+
+    let (direction, data) = rest
+        .trim_start()
+        .split_once(' ')
+        .ok_or(TranscriptErrorKind::InvalidDirection)?;
+    let direction = match direction {
+        "TX" => Direction::Tx,
+        "RX" => Direction::Rx,
+        _ => return Err(TranscriptErrorKind::InvalidDirection),
+    };
+
+    let data = data
+        .trim_start()
+        .strip_prefix('"')
+        .and_then(|data| data.strip_suffix('"'))
+        .ok_or(TranscriptErrorKind::InvalidData)?;
+
+    Ok(Entry {
+        time_ms,
+        direction,
+        data: unescape(data.as_bytes())?,
+    })
+}
+
+fn unescape(mut text: &[u8]) -> Result<Vec<u8>, TranscriptErrorKind> {
+    let mut data = Vec::with_capacity(text.len());
+
+    loop {
+        let (byte, rest) = match text {
+            [] => return Ok(data),
+            [b'\\', b'r', rest @ ..] => (b'\r', rest),
+            [b'\\', b'n', rest @ ..] => (b'\n', rest),
+            [b'\\', b't', rest @ ..] => (b'\t', rest),
+            [b'\\', b'"', rest @ ..] => (b'"', rest),
+            [b'\\', b'\\', rest @ ..] => (b'\\', rest),
+            [b'\\', b'x', high, low, rest @ ..] => {
+                let hex = [*high, *low];
+                let hex =
+                    core::str::from_utf8(&hex).map_err(|_| TranscriptErrorKind::InvalidEscape)?;
+                let byte =
+                    u8::from_str_radix(hex, 16).map_err(|_| TranscriptErrorKind::InvalidEscape)?;
+                (byte, rest)
+            }
+            [b'\\', ..] => return Err(TranscriptErrorKind::InvalidEscape),
+            // A quote in the data must be escaped
+            [b'"', ..] => return Err(TranscriptErrorKind::InvalidData),
+            [byte, rest @ ..] => (*byte, rest),
+        };
+
+        data.push(byte);
+        text = rest;
+    }
+}
+
+/// Displays the data with the escaping of the transcript format
+struct Escaped<'a>(&'a [u8]);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            match byte {
+                b'\r' => f.write_str("\\r")?,
+                b'\n' => f.write_str("\\n")?,
+                b'\t' => f.write_str("\\t")?,
+                b'"' => f.write_str("\\\"")?,
+                b'\\' => f.write_str("\\\\")?,
+                b' '..=b'~' => write!(f, "{}", *byte as char)?,
+                _ => write!(f, "\\x{:02X}", byte)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// # Recorder
+/// Records everything that is read from and written to an interface into a [Transcript]
+///
+/// The reads and writes that follow each other in the same direction are put in the same entry,
+/// until the data ends with a line feed.
+///
+/// ## Example
+/// ```rust
+/// use at_commands::transcript::{Direction, Recorder};
+/// use std::io::{Read, Write};
+///
+/// // Anything that implements `Read` and `Write`, like a serial port
+/// let device = std::io::Cursor::new(b"\r\nOK\r\n".to_vec());
+/// let mut recorder = Recorder::new(device);
+///
+/// let mut buffer = [0; 16];
+/// let length = recorder.read(&mut buffer).unwrap();
+///
+/// let (_, transcript) = recorder.finish();
+/// assert_eq!(transcript.data(Direction::Rx), &buffer[..length]);
+/// ```
+pub struct Recorder<T> {
+    inner: T,
+    start: Instant,
+    transcript: Transcript,
+}
+
+impl<T> Recorder<T> {
+    /// Starts recording the interface
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            start: Instant::now(),
+            transcript: Transcript::new(),
+        }
+    }
+
+    /// The transcript so far
+    pub fn transcript(&self) -> &Transcript {
+        &self.transcript
+    }
+
+    /// Stops recording and gives back the interface and the transcript
+    pub fn finish(self) -> (T, Transcript) {
+        (self.inner, self.transcript)
+    }
+
+    fn record(&mut self, direction: Direction, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        match self.transcript.entries.last_mut() {
+            Some(last) if last.direction == direction && last.data.last() != Some(&b'\n') => {
+                last.data.extend_from_slice(data)
+            }
+            _ => {
+                let time_ms = self
+                    .start
+                    .elapsed()
+                    .as_millis()
+                    .try_into()
+                    .unwrap_or(u64::MAX);
+                self.transcript.push(time_ms, direction, data);
+            }
+        }
+    }
+}
+
+impl<T: io::Read> io::Read for Recorder<T> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let length = self.inner.read(buffer)?;
+        self.record(Direction::Rx, &buffer[..length]);
+        Ok(length)
+    }
+}
+
+impl<T: io::Write> io::Write for Recorder<T> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let length = self.inner.write(buffer)?;
+        self.record(Direction::Tx, &buffer[..length]);
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// # Replay
+/// Replays a transcript to test the code that talks to a device
+///
+/// The commands the code sends are checked against what the host sent in the transcript,
+/// and the code gets what the device sent in return.
+///
+/// ## Example
+/// ```rust
+/// use at_commands::builder::CommandBuilder;
+/// use at_commands::parser::CommandParser;
+/// use at_commands::transcript::{Replay, Transcript};
+///
+/// let transcript = Transcript::parse(
+///     "0 TX \"AT+CSQ\\r\\n\"\n\
+///      12 RX \"\\r\\n+CSQ: 20,99\\r\\n\\r\\nOK\\r\\n\"\n",
+/// )
+/// .unwrap();
+/// let mut replay = Replay::new(&transcript);
+///
+/// let mut buffer = [0; 32];
+/// let command = CommandBuilder::create_execute(&mut buffer, true)
+///     .named("+CSQ")
+///     .finish()
+///     .unwrap();
+///
+/// let response = replay.exchange(command).unwrap();
+/// let (rssi, ber) = CommandParser::parse(response)
+///     .expect_identifier(b"\r\n+CSQ:")
+///     .expect_int_parameter()
+///     .expect_int_parameter()
+///     .expect_identifier(b"\r\n\r\nOK\r\n")
+///     .finish()
+///     .unwrap();
+///
+/// assert_eq!((rssi, ber), (20, 99));
+/// replay.finish().unwrap();
+/// ```
+pub struct Replay<'t> {
+    entries: &'t [Entry],
+    /// The entry that is sent next
+    tx_entry: usize,
+    /// The entry that is received next
+    rx_entry: usize,
+    received: Vec<u8>,
+}
+
+/// The ways a replay can differ from the transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The code sent something else than the host in the transcript
+    Mismatch {
+        /// The index of the first entry of what the host sent
+        entry: usize,
+        /// What the host sent in the transcript
+        expected: Vec<u8>,
+        /// What the code sent
+        sent: Vec<u8>,
+    },
+    /// The code sent something after the transcript ended
+    End,
+    /// The code didn't replay the whole transcript.
+    /// The entry with the given index is the first that didn't happen.
+    Unfinished {
+        /// The index of the entry
+        entry: usize,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Mismatch {
+                entry,
+                expected,
+                sent,
+            } => write!(
+                f,
+                "entry {}: expected \"{}\" but \"{}\" was sent",
+                entry,
+                Escaped(expected),
+                Escaped(sent)
+            ),
+            ReplayError::End => write!(f, "data was sent after the end of the transcript"),
+            ReplayError::Unfinished { entry } => {
+                write!(f, "the transcript was not replayed from entry {}", entry)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl<'t> Replay<'t> {
+    /// Starts replaying the transcript from the start
+    pub fn new(transcript: &'t Transcript) -> Self {
+        Self {
+            entries: &transcript.entries,
+            tx_entry: 0,
+            rx_entry: 0,
+            received: Vec::new(),
+        }
+    }
+
+    /// Checks that the command is what the host sent next in the transcript.
+    ///
+    /// The command must be all that the host sent in the entries that follow each other.
+    pub fn send(&mut self, command: &[u8]) -> Result<(), ReplayError> {
+        let first = self.entries[self.tx_entry..]
+            .iter()
+            .position(|entry| entry.direction == Direction::Tx)
+            .map(|position| self.tx_entry + position)
+            .ok_or(ReplayError::End)?;
+        let length = self.entries[first..]
+            .iter()
+            .take_while(|entry| entry.direction == Direction::Tx)
+            .count();
+
+        let expected: Vec<u8> = self.entries[first..first + length]
+            .iter()
+            .flat_map(|entry| entry.data.iter().copied())
+            .collect();
+
+        if expected != command {
+            return Err(ReplayError::Mismatch {
+                entry: first,
+                expected,
+                sent: command.to_vec(),
+            });
+        }
+
+        self.tx_entry = first + length;
+        Ok(())
+    }
+
+    /// Gives what the device sent in the transcript since the last time,
+    /// up to the next time the host sends something.
+    pub fn receive(&mut self) -> &[u8] {
+        self.received.clear();
+
+        while let Some(entry) = self.entries.get(self.rx_entry) {
+            match entry.direction {
+                Direction::Rx => self.received.extend_from_slice(&entry.data),
+                // The device can't answer what the host hasn't sent yet
+                Direction::Tx if self.rx_entry >= self.tx_entry => break,
+                Direction::Tx => {}
+            }
+            self.rx_entry += 1;
+        }
+
+        &self.received
+    }
+
+    /// Sends the command and gives what the device sent in return
+    pub fn exchange(&mut self, command: &[u8]) -> Result<&[u8], ReplayError> {
+        self.send(command)?;
+        Ok(self.receive())
+    }
+
+    /// Checks that the whole transcript was replayed
+    pub fn finish(mut self) -> Result<(), ReplayError> {
+        let tx_entry = self.tx_entry;
+        let rx_remaining = self.receive().is_empty();
+
+        match self.entries[tx_entry..]
+            .iter()
+            .position(|entry| entry.direction == Direction::Tx)
+        {
+            Some(position) => Err(ReplayError::Unfinished {
+                entry: tx_entry + position,
+            }),
+            None if !rx_remaining => Err(ReplayError::Unfinished {
+                entry: self.entries.len(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::CommandBuilder;
+    use crate::parser::CommandParser;
+    use crate::result_code::FinalResult;
+
+    const TRANSCRIPT: &str = r#"
+# Boot of the modem
+0 RX "\r\nRDY\r\n"
+1500 TX "ATE0\r\n"
+1502 RX "ATE0\r\r\nOK\r\n"
+1600 TX "AT+CGDCONT=1,\"IP\",\"internet\"\r\n"
+1700 RX "\r\nOK\r\n"
+1800 TX "AT+CMGS=5\r"
+1810 RX "\r\n> "
+1900 TX "hello\x1A"
+5000 RX "\r\n+CMGS: 7\r\n\r\nOK\r\n"
+"#;
+
+    #[test]
+    fn test_parse() {
+        let transcript = Transcript::parse(TRANSCRIPT).unwrap();
+
+        assert_eq!(transcript.entries.len(), 9);
+        assert_eq!(
+            transcript.entries[3],
+            Entry {
+                time_ms: 1600,
+                direction: Direction::Tx,
+                data: b"AT+CGDCONT=1,\"IP\",\"internet\"\r\n".to_vec(),
+            }
+        );
+        assert_eq!(transcript.entries[7].data, b"hello\x1A");
+
+        // Writing and parsing it again gives the same transcript
+        assert_eq!(
+            Transcript::parse(&transcript.to_string()).unwrap(),
+            transcript
+        );
+        assert!(transcript.to_string().contains("\"hello\\x1A\""));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (text, kind) in [
+            ("TX \"AT\"", TranscriptErrorKind::InvalidTime),
+            ("-1 TX \"AT\"", TranscriptErrorKind::InvalidTime),
+            ("0 XX \"AT\"", TranscriptErrorKind::InvalidDirection),
+            ("0 TX", TranscriptErrorKind::InvalidDirection),
+            ("0 TX AT", TranscriptErrorKind::InvalidData),
+            ("0 TX \"A\"T\"", TranscriptErrorKind::InvalidData),
+            ("0 TX \"AT\\q\"", TranscriptErrorKind::InvalidEscape),
+            ("0 TX \"AT\\xG0\"", TranscriptErrorKind::InvalidEscape),
+        ] {
+            assert_eq!(
+                Transcript::parse(&format!("# Comment\n\n{}", text)),
+                Err(TranscriptError { line: 3, kind }),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_replay() {
+        let transcript = Transcript::parse(TRANSCRIPT).unwrap();
+        let mut replay = Replay::new(&transcript);
+        let mut buffer = [0; 64];
+
+        assert_eq!(replay.receive(), b"\r\nRDY\r\n");
+
+        let command = CommandBuilder::create_basic(&mut buffer, true)
+            .named("E")
+            .with_value(0)
+            .finish()
+            .unwrap();
+        let (result,) = CommandParser::parse(replay.exchange(command).unwrap())
+            .expect_identifier(b"ATE0\r")
+            .expect_final_result()
+            .finish()
+            .unwrap();
+        assert_eq!(result, FinalResult::Ok);
+
+        let command = CommandBuilder::create_set(&mut buffer, true)
+            .named("+CGDCONT")
+            .with_int_parameter(1)
+            .with_string_parameter("IP")
+            .with_string_parameter("internet")
+            .finish()
+            .unwrap();
+        assert_eq!(replay.exchange(command).unwrap(), b"\r\nOK\r\n");
+
+        let command = CommandBuilder::create_set(&mut buffer, true)
+            .named("+CMGS")
+            .with_int_parameter(5)
+            .finish_with(b"\r")
+            .unwrap();
+        CommandParser::parse(replay.exchange(command).unwrap())
+            .expect_prompt()
+            .finish()
+            .unwrap();
+
+        let payload = CommandBuilder::create_payload(&mut buffer)
+            .with_data(b"hello")
+            .finish_with_ctrl_z()
+            .unwrap();
+        let (reference, _) = CommandParser::parse(replay.exchange(payload).unwrap())
+            .expect_identifier(b"\r\n+CMGS:")
+            .expect_int_parameter()
+            .expect_final_result()
+            .finish()
+            .unwrap();
+        assert_eq!(reference, 7);
+
+        replay.finish().unwrap();
+    }
+
+    #[test]
+    fn test_replay_errors() {
+        let transcript = Transcript::parse(TRANSCRIPT).unwrap();
+
+        let mut replay = Replay::new(&transcript);
+        assert_eq!(
+            replay.send(b"ATE1\r\n"),
+            Err(ReplayError::Mismatch {
+                entry: 1,
+                expected: b"ATE0\r\n".to_vec(),
+                sent: b"ATE1\r\n".to_vec(),
+            })
+        );
+        replay.send(b"ATE0\r\n").unwrap();
+        assert_eq!(replay.finish(), Err(ReplayError::Unfinished { entry: 3 }));
+
+        // The answer to the last command was never received
+        let transcript = Transcript::parse("0 TX \"AT\\r\\n\"\n1 RX \"\\r\\nOK\\r\\n\"").unwrap();
+        let mut replay = Replay::new(&transcript);
+        replay.send(b"AT\r\n").unwrap();
+        assert_eq!(replay.send(b"AT\r\n"), Err(ReplayError::End));
+        assert_eq!(replay.finish(), Err(ReplayError::Unfinished { entry: 2 }));
+    }
+
+    #[test]
+    fn test_recorder() {
+        use std::io::{Read, Write};
+
+        let mut recorder = Recorder::new(io::Cursor::new(Vec::new()));
+        recorder.write_all(b"AT").unwrap();
+        recorder.write_all(b"+CSQ\r\n").unwrap();
+        recorder.write_all(b"AT\r\n").unwrap();
+
+        let (device, transcript) = recorder.finish();
+        assert_eq!(device.into_inner(), b"AT+CSQ\r\nAT\r\n");
+        assert_eq!(transcript.entries.len(), 2);
+        assert_eq!(transcript.entries[0].data, b"AT+CSQ\r\n");
+
+        let mut recorder = Recorder::new(io::Cursor::new(b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n"));
+        let mut buffer = [0; 5];
+        while recorder.read(&mut buffer).unwrap() > 0 {}
+
+        let (_, transcript) = recorder.finish();
+        assert_eq!(
+            transcript.data(Direction::Rx),
+            b"\r\n+CSQ: 20,99\r\n\r\nOK\r\n"
+        );
+        assert!(transcript
+            .entries
+            .iter()
+            .all(|entry| entry.direction == Direction::Rx));
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn test_mock_steps() {
+        use crate::client::AtClient;
+        use crate::mock::{MockModem, Step};
+
+        let transcript = Transcript::parse(
+            "0 TX \"AT+COPS?\\r\\n\"\n200 RX \"\\r\\n+COPS: 0\\r\\n\\r\\nOK\\r\\n\"",
+        )
+        .unwrap();
+        let steps = transcript.steps();
+        assert_eq!(steps[1], Step::Delay(200));
+
+        let modem = MockModem::new(&steps);
+        let mut client = AtClient::<_, _, 64>::new(modem.io(), modem.clock(), 100);
+        assert!(client.send(b"AT+COPS?\r\n").is_err());
+
+        let modem = MockModem::new(&steps);
+        let mut client = AtClient::<_, _, 64>::new(modem.io(), modem.clock(), 1000);
+        assert_eq!(
+            client.send(b"AT+COPS?\r\n").unwrap().result(),
+            Some(FinalResult::Ok)
+        );
+        assert_eq!(modem.finish(), Ok(()));
+    }
+}
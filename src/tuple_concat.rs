@@ -247,3 +247,167 @@ impl<T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, C> Tu
         )
     }
 }
+
+pub trait TupleLast {
+    type Last: Copy;
+    fn last(&self) -> Self::Last;
+}
+
+impl<T0: Copy> TupleLast for (T0,) {
+    type Last = T0;
+    fn last(&self) -> Self::Last {
+        self.0
+    }
+}
+
+impl<T0, T1: Copy> TupleLast for (T0, T1) {
+    type Last = T1;
+    fn last(&self) -> Self::Last {
+        self.1
+    }
+}
+
+impl<T0, T1, T2: Copy> TupleLast for (T0, T1, T2) {
+    type Last = T2;
+    fn last(&self) -> Self::Last {
+        self.2
+    }
+}
+
+impl<T0, T1, T2, T3: Copy> TupleLast for (T0, T1, T2, T3) {
+    type Last = T3;
+    fn last(&self) -> Self::Last {
+        self.3
+    }
+}
+
+impl<T0, T1, T2, T3, T4: Copy> TupleLast for (T0, T1, T2, T3, T4) {
+    type Last = T4;
+    fn last(&self) -> Self::Last {
+        self.4
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5: Copy> TupleLast for (T0, T1, T2, T3, T4, T5) {
+    type Last = T5;
+    fn last(&self) -> Self::Last {
+        self.5
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6: Copy> TupleLast for (T0, T1, T2, T3, T4, T5, T6) {
+    type Last = T6;
+    fn last(&self) -> Self::Last {
+        self.6
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6, T7: Copy> TupleLast for (T0, T1, T2, T3, T4, T5, T6, T7) {
+    type Last = T7;
+    fn last(&self) -> Self::Last {
+        self.7
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6, T7, T8: Copy> TupleLast for (T0, T1, T2, T3, T4, T5, T6, T7, T8) {
+    type Last = T8;
+    fn last(&self) -> Self::Last {
+        self.8
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6, T7, T8, T9: Copy> TupleLast
+    for (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9)
+{
+    type Last = T9;
+    fn last(&self) -> Self::Last {
+        self.9
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10: Copy> TupleLast
+    for (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10)
+{
+    type Last = T10;
+    fn last(&self) -> Self::Last {
+        self.10
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11: Copy> TupleLast
+    for (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11)
+{
+    type Last = T11;
+    fn last(&self) -> Self::Last {
+        self.11
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12: Copy> TupleLast
+    for (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12)
+{
+    type Last = T12;
+    fn last(&self) -> Self::Last {
+        self.12
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13: Copy> TupleLast
+    for (T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13)
+{
+    type Last = T13;
+    fn last(&self) -> Self::Last {
+        self.13
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14: Copy> TupleLast
+    for (
+        T0,
+        T1,
+        T2,
+        T3,
+        T4,
+        T5,
+        T6,
+        T7,
+        T8,
+        T9,
+        T10,
+        T11,
+        T12,
+        T13,
+        T14,
+    )
+{
+    type Last = T14;
+    fn last(&self) -> Self::Last {
+        self.14
+    }
+}
+
+impl<T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15: Copy> TupleLast
+    for (
+        T0,
+        T1,
+        T2,
+        T3,
+        T4,
+        T5,
+        T6,
+        T7,
+        T8,
+        T9,
+        T10,
+        T11,
+        T12,
+        T13,
+        T14,
+        T15,
+    )
+{
+    type Last = T15;
+    fn last(&self) -> Self::Last {
+        self.15
+    }
+}
@@ -0,0 +1,335 @@
+//! Module that routes unsolicited result codes (URCs) to their handlers
+
+use crate::digester::DigestItem;
+
+/// A handler for unsolicited result codes
+///
+/// It is implemented for all closures that take the line, so those can be registered directly.
+pub trait UrcHandler {
+    /// Handles a line that starts with the prefix the handler was registered with.
+    ///
+    /// The line does not contain the line terminator.
+    fn handle(&mut self, line: &[u8]);
+}
+
+impl<F: FnMut(&[u8])> UrcHandler for F {
+    fn handle(&mut self, line: &[u8]) {
+        self(line)
+    }
+}
+
+/// # UrcDispatcher
+/// Routes incoming lines to the registered URC handlers or to the pending command response
+///
+/// The handlers are stored in a table with room for `N` of them, so nothing is allocated.
+/// A line goes to the first handler whose prefix it starts with.
+/// Lines that don't match any prefix are part of the response of the pending command.
+///
+/// Some lines are both a URC and the response to a command, like `+CREG: 2,1` for `AT+CREG?`.
+/// With [Self::dispatch_for] the response prefix of the pending command is given,
+/// so those lines go to the response instead of the handler.
+///
+/// ## Example
+/// ```rust
+/// use at_commands::parser::CommandParser;
+/// use at_commands::urc::{Dispatch, UrcDispatcher};
+///
+/// let mut registration_status = None;
+/// let mut rings = 0;
+///
+/// let mut on_creg = |line: &[u8]| {
+///     if let Ok((status,)) = CommandParser::parse(line)
+///         .expect_identifier(b"+CREG:")
+///         .expect_int_parameter()
+///         .finish()
+///     {
+///         registration_status = Some(status);
+///     }
+/// };
+/// let mut on_ring = |_: &[u8]| rings += 1;
+///
+/// let mut dispatcher = UrcDispatcher::<4>::new();
+/// dispatcher.register(b"+CREG:", &mut on_creg).unwrap();
+/// dispatcher.register(b"RING", &mut on_ring).unwrap();
+///
+/// assert_eq!(dispatcher.dispatch(b"+CREG: 1"), Dispatch::Urc);
+/// assert_eq!(dispatcher.dispatch(b"RING"), Dispatch::Urc);
+/// assert_eq!(dispatcher.dispatch(b"+CSQ: 20,99"), Dispatch::Response(b"+CSQ: 20,99"));
+///
+/// assert_eq!(registration_status, Some(1));
+/// assert_eq!(rings, 1);
+/// ```
+pub struct UrcDispatcher<'h, const N: usize> {
+    handlers: [Option<(&'h [u8], &'h mut dyn UrcHandler)>; N],
+}
+
+impl<'h, const N: usize> UrcDispatcher<'h, N> {
+    /// Creates a dispatcher without any handlers
+    pub fn new() -> Self {
+        Self {
+            handlers: core::array::from_fn(|_| None),
+        }
+    }
+
+    /// Registers a handler for the lines that start with the prefix.
+    ///
+    /// Returns an error if the table is full.
+    pub fn register(
+        &mut self,
+        prefix: &'h [u8],
+        handler: &'h mut dyn UrcHandler,
+    ) -> Result<(), TableFull> {
+        match self.handlers.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some((prefix, handler));
+                Ok(())
+            }
+            None => Err(TableFull),
+        }
+    }
+
+    /// Removes the handlers that are registered with the prefix.
+    pub fn unregister(&mut self, prefix: &[u8]) {
+        for slot in self.handlers.iter_mut() {
+            if matches!(slot, Some((registered, _)) if *registered == prefix) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Returns true if the line starts with the prefix of one of the handlers
+    pub fn is_urc(&self, line: &[u8]) -> bool {
+        self.handlers
+            .iter()
+            .flatten()
+            .any(|(prefix, _)| line.starts_with(prefix))
+    }
+
+    /// Gives the line to the handler it belongs to.
+    ///
+    /// If no handler matches, the line is given back as part of the response.
+    pub fn dispatch<'l>(&mut self, line: &'l [u8]) -> Dispatch<'l> {
+        self.dispatch_for(line, None)
+    }
+
+    /// Gives the line to the handler it belongs to,
+    /// unless it starts with the response prefix of the pending command, like `+CREG:` for `AT+CREG?`.
+    ///
+    /// If the line is part of the response, it is given back.
+    ///
+    /// ```rust
+    /// use at_commands::urc::{Dispatch, UrcDispatcher};
+    ///
+    /// let mut registrations = 0;
+    /// let mut on_creg = |_: &[u8]| registrations += 1;
+    ///
+    /// let mut dispatcher = UrcDispatcher::<1>::new();
+    /// dispatcher.register(b"+CREG:", &mut on_creg).unwrap();
+    ///
+    /// // The answer to `AT+CREG?`
+    /// assert_eq!(
+    ///     dispatcher.dispatch_for(b"+CREG: 2,1", Some(b"+CREG:")),
+    ///     Dispatch::Response(b"+CREG: 2,1")
+    /// );
+    /// // A URC while another command is pending
+    /// assert_eq!(dispatcher.dispatch_for(b"+CREG: 1", Some(b"+CSQ:")), Dispatch::Urc);
+    ///
+    /// assert_eq!(registrations, 1);
+    /// ```
+    pub fn dispatch_for<'l>(
+        &mut self,
+        line: &'l [u8],
+        response_prefix: Option<&[u8]>,
+    ) -> Dispatch<'l> {
+        if response_prefix.is_some_and(|prefix| line.starts_with(prefix)) {
+            return Dispatch::Response(line);
+        }
+
+        match self
+            .handlers
+            .iter_mut()
+            .flatten()
+            .find(|(prefix, _)| line.starts_with(prefix))
+        {
+            Some((_, handler)) => {
+                handler.handle(line);
+                Dispatch::Urc
+            }
+            None => Dispatch::Response(line),
+        }
+    }
+
+    /// Dispatches the line of an item of the [Digester](crate::digester::Digester).
+    ///
+    /// Returns None if the item was a URC, otherwise the item is given back.
+    pub fn dispatch_item<'l>(&mut self, item: DigestItem<'l>) -> Option<DigestItem<'l>> {
+        self.dispatch_item_for(item, None)
+    }
+
+    /// Dispatches the line of an item of the [Digester](crate::digester::Digester)
+    /// like [Self::dispatch_for] does.
+    ///
+    /// Returns None if the item was a URC, otherwise the item is given back.
+    pub fn dispatch_item_for<'l>(
+        &mut self,
+        item: DigestItem<'l>,
+        response_prefix: Option<&[u8]>,
+    ) -> Option<DigestItem<'l>> {
+        match item {
+            DigestItem::Line(line) => match self.dispatch_for(line, response_prefix) {
+                Dispatch::Urc => None,
+                Dispatch::Response(line) => Some(DigestItem::Line(line)),
+            },
+            item => Some(item),
+        }
+    }
+}
+
+impl<'h, const N: usize> Default for UrcDispatcher<'h, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where a line went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Dispatch<'l> {
+    /// The line was a URC and was given to its handler
+    Urc,
+    /// The line is part of the response of the pending command
+    Response(&'l [u8]),
+}
+
+/// Error for when there's no room for another handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TableFull;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digester::Digester;
+    use crate::result_code::FinalResult;
+
+    struct SocketData {
+        received: [Option<i32>; 2],
+    }
+
+    impl UrcHandler for SocketData {
+        fn handle(&mut self, line: &[u8]) {
+            if let Ok((_, socket)) = crate::parser::CommandParser::parse(line)
+                .expect_identifier(b"+QIURC:")
+                .expect_string_parameter()
+                .expect_int_parameter()
+                .finish()
+            {
+                if let Some(slot) = self.received.iter_mut().find(|slot| slot.is_none()) {
+                    *slot = Some(socket);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_trait_handler() {
+        let mut socket_data = SocketData {
+            received: [None; 2],
+        };
+
+        let mut dispatcher = UrcDispatcher::<1>::new();
+        dispatcher.register(b"+QIURC:", &mut socket_data).unwrap();
+
+        assert!(dispatcher.is_urc(b"+QIURC: \"recv\",0"));
+        assert_eq!(dispatcher.dispatch(b"+QIURC: \"recv\",0"), Dispatch::Urc);
+        assert_eq!(dispatcher.dispatch(b"+QIURC: \"recv\",3"), Dispatch::Urc);
+        assert_eq!(
+            dispatcher.dispatch(b"+QIRD: 0"),
+            Dispatch::Response(b"+QIRD: 0")
+        );
+
+        assert_eq!(socket_data.received, [Some(0), Some(3)]);
+    }
+
+    #[test]
+    fn test_table() {
+        let mut first = |_: &[u8]| {};
+        let mut second = |_: &[u8]| {};
+        let mut third = |_: &[u8]| {};
+        let mut fourth = |_: &[u8]| {};
+
+        let mut dispatcher = UrcDispatcher::<2>::new();
+        assert_eq!(dispatcher.register(b"+CREG:", &mut first), Ok(()));
+        assert_eq!(dispatcher.register(b"+CEREG:", &mut second), Ok(()));
+        assert_eq!(dispatcher.register(b"RING", &mut fourth), Err(TableFull));
+
+        dispatcher.unregister(b"+CREG:");
+        assert!(!dispatcher.is_urc(b"+CREG: 1"));
+        assert!(dispatcher.is_urc(b"+CEREG: 1"));
+        assert_eq!(dispatcher.register(b"RING", &mut third), Ok(()));
+        assert!(dispatcher.is_urc(b"RING"));
+    }
+
+    #[test]
+    fn test_digester_items() {
+        let mut rings = 0;
+        let mut on_ring = |_: &[u8]| rings += 1;
+
+        let mut dispatcher = UrcDispatcher::<1>::new();
+        dispatcher.register(b"RING", &mut on_ring).unwrap();
+
+        let mut digester = Digester::<64>::new();
+        digester.feed(b"\r\nRING\r\n\r\n+CSQ: 20,99\r\n\r\nRING\r\n\r\nOK\r\n");
+
+        let mut response_items = 0;
+        while let Some(item) = digester.next_item() {
+            match dispatcher.dispatch_item(item) {
+                Some(DigestItem::Line(line)) => assert_eq!(line, b"+CSQ: 20,99"),
+                Some(DigestItem::FinalResult(result)) => assert_eq!(result, FinalResult::Ok),
+                Some(item) => panic!("Unexpected item: {:?}", item),
+                None => continue,
+            }
+            response_items += 1;
+        }
+
+        assert_eq!(rings, 2);
+        assert_eq!(response_items, 2);
+    }
+
+    #[test]
+    fn test_pending_response() {
+        let mut registrations = 0;
+        let mut on_creg = |_: &[u8]| registrations += 1;
+
+        let mut dispatcher = UrcDispatcher::<1>::new();
+        dispatcher.register(b"+CREG:", &mut on_creg).unwrap();
+
+        // The response to `AT+CREG?` with a URC in front of it
+        let mut digester = Digester::<64>::new();
+        digester.feed(b"\r\n+CREG: 1\r\nAT+CREG?\r\r\n+CREG: 2,1\r\n\r\nOK\r\n");
+
+        let mut response = None;
+        let mut pending = None;
+        while let Some(item) = digester.next_item() {
+            match dispatcher.dispatch_item_for(item, pending) {
+                Some(DigestItem::Echo(_)) => pending = Some(b"+CREG:"),
+                Some(DigestItem::Line(line)) => {
+                    response = Some(
+                        crate::parser::CommandParser::parse(line)
+                            .expect_identifier(b"+CREG:")
+                            .expect_int_parameter()
+                            .expect_int_parameter()
+                            .finish()
+                            .unwrap(),
+                    );
+                }
+                Some(DigestItem::FinalResult(result)) => assert_eq!(result, FinalResult::Ok),
+                Some(item) => panic!("Unexpected item: {:?}", item),
+                None => {}
+            }
+        }
+
+        assert_eq!(response, Some((2, 1)));
+        assert_eq!(registrations, 1);
+    }
+}